@@ -0,0 +1,142 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+
+use crate::colliders::PhysicsObject;
+use crate::components::Player;
+use crate::controller_avian::RenderPlayer;
+use crate::interact::{Interact, InteractAppExt, Verb};
+use crate::layers::GameLayer;
+
+/// Force the player applies to a prop they walk into, in newtons.
+const PUSH_FORCE: f32 = 60.0;
+/// Half the height of the player's collider. Props whose centre is below the player's feet are
+/// being stood on, not walked into.
+const PLAYER_HALF_HEIGHT: f32 = 1.5;
+/// Distance in front of the camera a held prop is kept at.
+const HOLD_DISTANCE: f32 = 2.0;
+/// How quickly a held prop closes the gap to its hold point, per second.
+const HOLD_GAIN: f32 = 12.0;
+const HOLD_MAX_SPEED: f32 = 15.0;
+/// A held prop that ends up this far from its hold point is considered obstructed and dropped.
+const HOLD_BREAK_DISTANCE: f32 = 1.5;
+/// Props heavier than this (in kilograms) cannot be picked up.
+const MAX_CARRY_MASS: f32 = 40.0;
+const THROW_IMPULSE: f32 = 12.0;
+/// Seconds the interact key is held to pick a prop up, so a quick press on something that can
+/// also be talked to or used does not grab it.
+const PICK_UP_HOLD: f32 = 0.4;
+
+/// Marks the prop the player is currently carrying.
+#[derive(Component)]
+pub struct Held;
+
+pub struct CarryPlugin;
+
+impl Plugin for CarryPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_interaction::<PhysicsObject>(
+            Verb::new("pick_up", "Pick up").with_hold(PICK_UP_HOLD),
+            pick_up,
+        )
+        .add_systems(Update, (push_props_system, carry_held_system).chain());
+    }
+}
+
+/// Picks up `entity` if it is light enough. Returns whether it was picked up.
+pub fn grab(commands: &mut Commands, entity: Entity, mass: &ComputedMass) -> bool {
+    if mass.value() > MAX_CARRY_MASS {
+        info!(
+            "{:?} is too heavy to carry ({:.1} kg)",
+            entity,
+            mass.value()
+        );
+        return false;
+    }
+    commands
+        .entity(entity)
+        .insert((Held, GravityScale(0.0), GameLayer::held_prop()));
+    true
+}
+
+fn pick_up(In(interact): In<Interact>, mut commands: Commands, prop_q: Query<&ComputedMass>) {
+    if let Ok(mass) = prop_q.get(interact.target) {
+        grab(&mut commands, interact.target, mass);
+    }
+}
+
+pub fn release(commands: &mut Commands, entity: Entity) {
+    commands
+        .entity(entity)
+        .remove::<(Held, GravityScale)>()
+        .insert(GameLayer::prop());
+}
+
+#[allow(clippy::type_complexity)]
+fn push_props_system(
+    time: Res<Time>,
+    player_q: Query<(&LinearVelocity, &Position, &CollidingEntities), With<Player>>,
+    mut prop_q: Query<
+        (&mut LinearVelocity, &Position, &ComputedMass),
+        (With<PhysicsObject>, Without<Held>, Without<Player>),
+    >,
+) {
+    let Ok((player_vel, player_pos, colliding)) = player_q.single() else {
+        return;
+    };
+    let push_dir = Vec3::new(player_vel.x, 0.0, player_vel.z).normalize_or_zero();
+    if push_dir == Vec3::ZERO {
+        return;
+    }
+    for &other in colliding.iter() {
+        let Ok((mut vel, pos, mass)) = prop_q.get_mut(other) else {
+            continue;
+        };
+        let offset = pos.0 - player_pos.0;
+        // Only push what the player walks into, not what they walk away from or stand on.
+        if offset.y < -PLAYER_HALF_HEIGHT || push_dir.dot(offset.with_y(0.0)) <= 0.0 {
+            continue;
+        }
+        vel.0 += push_dir * PUSH_FORCE * mass.inverse() * time.delta_secs();
+    }
+}
+
+fn carry_held_system(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut contexts: EguiContexts,
+    camera_q: Query<&GlobalTransform, With<RenderPlayer>>,
+    mut held_q: Query<
+        (
+            Entity,
+            &Position,
+            &ComputedMass,
+            &mut LinearVelocity,
+            &mut AngularVelocity,
+        ),
+        With<Held>,
+    >,
+) {
+    let Ok(cam) = camera_q.single() else {
+        return;
+    };
+    // Clicks on the inventory or journal are not throws.
+    let throw = mouse.just_pressed(MouseButton::Left)
+        && !contexts.ctx_mut().is_ok_and(|ctx| ctx.wants_pointer_input());
+    for (e, pos, mass, mut vel, mut ang_vel) in held_q.iter_mut() {
+        if throw {
+            vel.0 = cam.forward() * THROW_IMPULSE * mass.inverse().min(1.0);
+            release(&mut commands, e);
+            continue;
+        }
+        let hold_point = cam.translation() + cam.forward() * HOLD_DISTANCE;
+        let to_hold = hold_point - pos.0;
+        if to_hold.length() > HOLD_BREAK_DISTANCE {
+            info!("Held object {:?} obstructed, dropping", e);
+            release(&mut commands, e);
+            continue;
+        }
+        vel.0 = (to_hold * HOLD_GAIN).clamp_length_max(HOLD_MAX_SPEED);
+        ang_vel.0 *= 0.5;
+    }
+}
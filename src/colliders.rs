@@ -0,0 +1,537 @@
+use crate::dialog::Npc;
+use crate::layers::GameLayer;
+use crate::trigger::Trigger;
+use crate::Interactable;
+use avian3d::prelude::*;
+use bevy::{
+    asset::LoadState,
+    mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+    prelude::*,
+};
+
+/// How the collider of a [`PhysicsObject`] is generated from its meshes.
+#[derive(Reflect, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PhysicsShape {
+    /// One box around each mesh.
+    #[default]
+    Box,
+    /// A sphere around all meshes. Use this for balls.
+    Sphere,
+    /// An upright (Y axis) capsule around all meshes. Use this for barrels and bottles.
+    Capsule,
+    /// The convex hull of all mesh vertices.
+    ConvexHull,
+}
+
+/// A dynamic prop. Every field can be set from Blender through Skein.
+///
+/// Keeps the `racing::PhysicsObject` type path it had before moving here, which the levels use.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+#[type_path = "racing"]
+pub struct PhysicsObject {
+    pub shape: PhysicsShape,
+    /// Total mass in kilograms. When zero the mass is computed from `density` instead.
+    pub mass: f32,
+    pub density: f32,
+    pub friction: f32,
+    pub restitution: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    /// Starts the body asleep, so stacked props stay put until something touches them.
+    pub start_asleep: bool,
+}
+
+impl Default for PhysicsObject {
+    fn default() -> Self {
+        Self {
+            shape: PhysicsShape::Box,
+            mass: 0.0,
+            density: 1.0,
+            friction: 0.5,
+            restitution: 0.0,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            start_asleep: false,
+        }
+    }
+}
+
+/// How the collider of a [`Ground`] entity is generated from its meshes.
+#[derive(Reflect, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GroundShape {
+    /// One box around each mesh. Cheap, but only correct for box-like geometry.
+    #[default]
+    Box,
+    /// The exact triangles of the meshes. Use this for terrain, ramps and other static geometry.
+    Trimesh,
+    /// A set of convex hulls approximating the meshes.
+    ConvexDecomposition,
+}
+
+/// Static level geometry. Keeps the `racing::Ground` type path the levels use.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+#[type_path = "racing"]
+pub struct Ground {
+    pub shape: GroundShape,
+}
+
+#[derive(Component)]
+pub struct GroundColliderComputed;
+
+/// How long an entity waits for its mesh assets before a collider is built from whatever
+/// has loaded so far.
+const MESH_LOAD_TIMEOUT_SECS: f32 = 10.0;
+
+/// Started on an entity whose collider is waiting on mesh assets.
+#[derive(Component)]
+struct ColliderLoadTimeout(Timer);
+
+/// Number of `Ground`, `PhysicsObject`, `Trigger` and `Interactable` entities whose collider has
+/// not been built yet.
+///
+/// A level should not be considered loaded while this is non-zero.
+#[derive(Resource, Default)]
+pub struct PendingColliders(pub usize);
+
+/// Builds colliders and updates [`PendingColliders`]. Systems that read the count should run
+/// after it, or they may see last frame's count.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ColliderReadiness;
+
+pub struct CollidersPlugin;
+
+impl Plugin for CollidersPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PhysicsObject>()
+            .register_type::<PhysicsShape>()
+            .register_type::<Ground>()
+            .register_type::<GroundShape>()
+            .init_resource::<PendingColliders>()
+            .add_systems(
+                Update,
+                (
+                    apply_physics_object_system,
+                    apply_ground_collider_system,
+                    apply_trigger_collider_system,
+                    apply_interaction_collider_system,
+                    count_pending_colliders,
+                )
+                    .chain()
+                    .in_set(ColliderReadiness),
+            );
+    }
+}
+
+/// Triangle geometry of one mesh, in the mesh's own space, together with the transform
+/// that takes it into the space of the entity the collider is built for.
+struct MeshPart {
+    to_root: Transform,
+    vertices: Vec<Vec3>,
+    indices: Vec<[u32; 3]>,
+}
+
+impl MeshPart {
+    fn from_mesh(mesh: &Mesh, to_root: Transform) -> Option<Self> {
+        if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+            return None;
+        }
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+        let vertices: Vec<Vec3> = positions.iter().map(|p| Vec3::from_array(*p)).collect();
+        let flat: Vec<u32> = match mesh.indices() {
+            Some(Indices::U16(i)) => i.iter().map(|&i| i as u32).collect(),
+            Some(Indices::U32(i)) => i.clone(),
+            None => (0..vertices.len() as u32).collect(),
+        };
+        let indices = flat
+            .chunks_exact(3)
+            .map(|tri| [tri[0], tri[1], tri[2]])
+            .collect();
+        Some(Self {
+            to_root,
+            vertices,
+            indices,
+        })
+    }
+}
+
+/// Merges all parts into a single vertex/index buffer in root space.
+fn merge_parts(parts: &[MeshPart]) -> (Vec<Vec3>, Vec<[u32; 3]>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for part in parts {
+        let base = vertices.len() as u32;
+        vertices.extend(
+            part.vertices
+                .iter()
+                .map(|v| part.to_root.transform_point(*v)),
+        );
+        indices.extend(
+            part.indices
+                .iter()
+                .map(|[a, b, c]| [a + base, b + base, c + base]),
+        );
+    }
+    (vertices, indices)
+}
+
+/// Result of walking an entity's hierarchy for mesh geometry.
+enum MeshParts {
+    /// Every mesh was loaded (or failed to load and was skipped).
+    Ready(Vec<MeshPart>),
+    /// At least one mesh is still loading. Holds the parts that are already available.
+    Pending(Vec<MeshPart>),
+}
+
+/// Collects the mesh of `root` and of every one of its descendants.
+fn collect_mesh_parts(
+    root: Entity,
+    children_q: &Query<&Children>,
+    node_q: &Query<(&Transform, Option<&Mesh3d>)>,
+    meshes: &Assets<Mesh>,
+    asset_server: &AssetServer,
+) -> MeshParts {
+    let mut parts = Vec::new();
+    let mut pending = false;
+    let mut todo = vec![(root, Transform::IDENTITY)];
+    while let Some((curr, to_root)) = todo.pop() {
+        if let Ok((_, Some(mesh3d))) = node_q.get(curr) {
+            match meshes.get(&mesh3d.0) {
+                Some(mesh) => match MeshPart::from_mesh(mesh, to_root) {
+                    Some(part) => parts.push(part),
+                    None => info!(
+                        "mesh of entity {:?} is not a triangle list with positions, skipping",
+                        curr
+                    ),
+                },
+                None => match asset_server.load_state(mesh3d.0.id()) {
+                    LoadState::Failed(err) => {
+                        warn!("mesh of entity {:?} failed to load: {}", curr, err)
+                    }
+                    _ => pending = true,
+                },
+            }
+        }
+        if let Ok(children) = children_q.get(curr) {
+            for child in children.iter() {
+                let local = node_q.get(child).map(|(t, _)| *t).unwrap_or_default();
+                todo.push((child, to_root * local));
+            }
+        }
+    }
+    if pending {
+        MeshParts::Pending(parts)
+    } else {
+        MeshParts::Ready(parts)
+    }
+}
+
+/// Returns the parts to build a collider from, or `None` if the entity should keep waiting
+/// on its mesh assets.
+fn parts_or_wait(
+    commands: &mut Commands,
+    entity: Entity,
+    parts: MeshParts,
+    timeout: Option<Mut<ColliderLoadTimeout>>,
+    time: &Time,
+) -> Option<Vec<MeshPart>> {
+    match (parts, timeout) {
+        (MeshParts::Ready(parts), timeout) => {
+            if timeout.is_some() {
+                commands.entity(entity).remove::<ColliderLoadTimeout>();
+            }
+            Some(parts)
+        }
+        (MeshParts::Pending(_), None) => {
+            commands
+                .entity(entity)
+                .insert(ColliderLoadTimeout(Timer::from_seconds(
+                    MESH_LOAD_TIMEOUT_SECS,
+                    TimerMode::Once,
+                )));
+            None
+        }
+        (MeshParts::Pending(parts), Some(mut timeout)) => {
+            if !timeout.0.tick(time.delta()).is_finished() {
+                return None;
+            }
+            warn!(
+                "entity {:?}: meshes still not loaded after {}s, building collider from {} loaded mesh(es)",
+                entity,
+                MESH_LOAD_TIMEOUT_SECS,
+                parts.len()
+            );
+            commands.entity(entity).remove::<ColliderLoadTimeout>();
+            Some(parts)
+        }
+    }
+}
+
+/// Builds a compound of one box per mesh part, each placed and oriented by the part's
+/// transform relative to the root entity.
+fn box_compound(parts: &[MeshPart]) -> Collider {
+    let boxes = parts
+        .iter()
+        .filter(|part| !part.vertices.is_empty())
+        .map(|part| {
+            let (min, max) = bounds(&part.vertices);
+            let size = ((max - min) * part.to_root.scale.abs()).max(Vec3::splat(MIN_BOX_SIZE));
+            (
+                Position(part.to_root.transform_point((min + max) * 0.5)),
+                Rotation(part.to_root.rotation),
+                Collider::cuboid(size.x, size.y, size.z),
+            )
+        })
+        .collect::<Vec<_>>();
+    if boxes.is_empty() {
+        return Collider::cuboid(0.5, 0.5, 0.5);
+    }
+    Collider::compound(boxes)
+}
+
+/// Returns the min and max corner of the box around `vertices`.
+fn bounds(vertices: &[Vec3]) -> (Vec3, Vec3) {
+    vertices.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), v| (min.min(*v), max.max(*v)),
+    )
+}
+
+/// Builds a single primitive around all parts, offset to the center of their bounds.
+fn centered_primitive(parts: &[MeshPart], shape: PhysicsShape) -> Collider {
+    let (vertices, _) = merge_parts(parts);
+    if vertices.is_empty() {
+        return Collider::cuboid(0.5, 0.5, 0.5);
+    }
+    let (min, max) = bounds(&vertices);
+    let half = ((max - min) * 0.5).max(Vec3::splat(MIN_BOX_SIZE * 0.5));
+    let primitive = match shape {
+        PhysicsShape::Sphere => Collider::sphere(half.max_element()),
+        _ => {
+            let radius = half.x.max(half.z);
+            Collider::capsule(radius, (half.y - radius).max(0.0) * 2.0)
+        }
+    };
+    Collider::compound(vec![(
+        Position((min + max) * 0.5),
+        Rotation::default(),
+        primitive,
+    )])
+}
+
+fn physics_object_collider(parts: &[MeshPart], shape: PhysicsShape) -> Collider {
+    match shape {
+        PhysicsShape::Box => box_compound(parts),
+        PhysicsShape::Sphere | PhysicsShape::Capsule => centered_primitive(parts, shape),
+        PhysicsShape::ConvexHull => {
+            let (vertices, _) = merge_parts(parts);
+            Collider::convex_hull(vertices).unwrap_or_else(|| box_compound(parts))
+        }
+    }
+}
+
+/// Flat meshes (planes, decals) would otherwise produce boxes without any thickness.
+const MIN_BOX_SIZE: f32 = 0.05;
+
+#[allow(clippy::type_complexity)]
+fn apply_ground_collider_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    meshes: Res<Assets<Mesh>>,
+    asset_server: Res<AssetServer>,
+    mut q: Query<
+        (
+            Entity,
+            &Ground,
+            Option<&Collider>,
+            Option<&RigidBody>,
+            Option<&mut ColliderLoadTimeout>,
+        ),
+        Without<GroundColliderComputed>,
+    >,
+    children_q: Query<&Children>,
+    node_q: Query<(&Transform, Option<&Mesh3d>)>,
+) {
+    for (e, ground, collider_opt, rb_opt, timeout) in q.iter_mut() {
+        // Doors and other animated level geometry keep their kinematic body.
+        if !matches!(rb_opt, Some(RigidBody::Static | RigidBody::Kinematic)) {
+            commands.entity(e).insert(RigidBody::Static);
+        }
+
+        if collider_opt.is_some() {
+            commands.entity(e).insert(GroundColliderComputed);
+            continue;
+        }
+
+        let parts = collect_mesh_parts(e, &children_q, &node_q, &meshes, &asset_server);
+        let Some(parts) = parts_or_wait(&mut commands, e, parts, timeout, &time) else {
+            continue;
+        };
+        if parts.is_empty() {
+            info!("ground entity {:?}: no descendant with mesh found", e);
+        }
+
+        let collider = match ground.shape {
+            GroundShape::Box => box_compound(&parts),
+            _ if parts.is_empty() => Collider::cuboid(0.5, 0.5, 0.5),
+            GroundShape::Trimesh => {
+                let (vertices, indices) = merge_parts(&parts);
+                Collider::trimesh(vertices, indices)
+            }
+            GroundShape::ConvexDecomposition => {
+                let (vertices, indices) = merge_parts(&parts);
+                Collider::convex_decomposition(vertices, indices)
+            }
+        };
+
+        info!(
+            "Ground collider added: entity={:?}, shape={:?}, meshes={}",
+            e,
+            ground.shape,
+            parts.len()
+        );
+
+        commands.entity(e).insert((collider, GameLayer::world()));
+        commands.entity(e).insert(GroundColliderComputed);
+    }
+}
+
+/// The rigid body is only inserted together with the collider, so a prop whose meshes are
+/// still loading does not fall through the level as a massless body.
+#[allow(clippy::type_complexity)]
+fn apply_physics_object_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    meshes: Res<Assets<Mesh>>,
+    asset_server: Res<AssetServer>,
+    mut q: Query<
+        (
+            Entity,
+            &PhysicsObject,
+            Option<&RigidBody>,
+            Option<&mut ColliderLoadTimeout>,
+        ),
+        Without<Collider>,
+    >,
+    children_q: Query<&Children>,
+    node_q: Query<(&Transform, Option<&Mesh3d>)>,
+) {
+    for (e, object, rb_opt, timeout) in q.iter_mut() {
+        let parts = collect_mesh_parts(e, &children_q, &node_q, &meshes, &asset_server);
+        let Some(parts) = parts_or_wait(&mut commands, e, parts, timeout, &time) else {
+            continue;
+        };
+        info!(
+            "PhysicsObject collider added: entity={:?}, shape={:?}, meshes={}",
+            e,
+            object.shape,
+            parts.len()
+        );
+        let mut entity = commands.entity(e);
+        entity.insert((
+            physics_object_collider(&parts, object.shape),
+            GameLayer::prop(),
+            ColliderDensity(object.density),
+            Friction::new(object.friction),
+            Restitution::new(object.restitution),
+            LinearDamping(object.linear_damping),
+            AngularDamping(object.angular_damping),
+        ));
+        if object.mass > 0.0 {
+            entity.insert(Mass(object.mass));
+        }
+        if object.start_asleep {
+            entity.insert(Sleeping);
+        }
+        if !matches!(rb_opt, Some(RigidBody::Dynamic)) {
+            entity.insert(RigidBody::Dynamic);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_trigger_collider_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    meshes: Res<Assets<Mesh>>,
+    asset_server: Res<AssetServer>,
+    mut q: Query<(Entity, Option<&mut ColliderLoadTimeout>), (With<Trigger>, Without<Collider>)>,
+    children_q: Query<&Children>,
+    node_q: Query<(&Transform, Option<&Mesh3d>)>,
+) {
+    for (e, timeout) in q.iter_mut() {
+        let parts = collect_mesh_parts(e, &children_q, &node_q, &meshes, &asset_server);
+        let Some(parts) = parts_or_wait(&mut commands, e, parts, timeout, &time) else {
+            continue;
+        };
+        info!(
+            "Trigger collider added: entity={:?}, meshes={}",
+            e,
+            parts.len()
+        );
+        commands
+            .entity(e)
+            .insert((box_compound(&parts), Sensor, GameLayer::trigger()));
+    }
+}
+
+/// Interactables that get no collider any other way.
+type NeedsInteractionCollider = (
+    With<Interactable>,
+    Without<Collider>,
+    Without<PhysicsObject>,
+    Without<Ground>,
+    Without<Trigger>,
+);
+
+/// Gives interactables without a collider of their own (NPCs, plain props) a sensor the
+/// interaction ray can hit. NPCs keep their `Npc` layers so triggers still see them; everything
+/// else goes on the interaction layer.
+fn apply_interaction_collider_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    meshes: Res<Assets<Mesh>>,
+    asset_server: Res<AssetServer>,
+    mut q: Query<
+        (Entity, Has<Npc>, Option<&mut ColliderLoadTimeout>),
+        NeedsInteractionCollider,
+    >,
+    children_q: Query<&Children>,
+    node_q: Query<(&Transform, Option<&Mesh3d>)>,
+) {
+    for (e, is_npc, timeout) in q.iter_mut() {
+        let parts = collect_mesh_parts(e, &children_q, &node_q, &meshes, &asset_server);
+        let Some(parts) = parts_or_wait(&mut commands, e, parts, timeout, &time) else {
+            continue;
+        };
+        let layers = if is_npc {
+            GameLayer::npc()
+        } else {
+            GameLayer::interaction_volume()
+        };
+        commands
+            .entity(e)
+            .insert((box_compound(&parts), Sensor, layers));
+    }
+}
+
+fn count_pending_colliders(
+    mut pending: ResMut<PendingColliders>,
+    ground_q: Query<(), (With<Ground>, Without<GroundColliderComputed>)>,
+    physics_q: Query<(), (With<PhysicsObject>, Without<Collider>)>,
+    trigger_q: Query<(), (With<Trigger>, Without<Collider>)>,
+    interaction_q: Query<(), NeedsInteractionCollider>,
+) {
+    let count = ground_q.iter().count()
+        + physics_q.iter().count()
+        + trigger_q.iter().count()
+        + interaction_q.iter().count();
+    if pending.0 != count {
+        pending.0 = count;
+    }
+}
@@ -129,6 +129,8 @@ pub struct FpsController {
     pub key_fly: KeyCode,
     pub key_crouch: KeyCode,
     pub experimental_enable_ledge_cling: bool,
+    /// Collision layers the ground and ledge casts consider solid
+    pub ground_mask: LayerMask,
 }
 
 impl Default for FpsController {
@@ -177,6 +179,7 @@ impl Default for FpsController {
             key_crouch: KeyCode::ControlLeft,
             sensitivity: 0.001,
             experimental_enable_ledge_cling: false, // Does not work well on Avian yet.
+            ground_mask: LayerMask::ALL,
         }
     }
 }
@@ -303,7 +306,8 @@ pub fn fps_controller_move(
 
                 // Shape cast downwards to find ground
                 // Better than a ray cast as it handles when you are near the edge of a surface
-                let filter = SpatialQueryFilter::default().with_excluded_entities([entity]);
+                let filter = SpatialQueryFilter::from_mask(controller.ground_mask)
+                    .with_excluded_entities([entity]);
                 if let Some(hit) = spatial_query_pipeline.cast_shape(
                     // Consider when the controller is right up against a wall
                     // We do not want the shape cast to detect it,
@@ -444,6 +448,7 @@ pub fn fps_controller_move(
                         // Find the component of our velocity that is overhanging and subtract it off
                         let overhang = overhang_component(
                             entity,
+                            controller.ground_mask,
                             &collider,
                             transform.as_ref(),
                             &spatial_query_pipeline,
@@ -457,6 +462,7 @@ pub fn fps_controller_move(
                     // If we are still overhanging consider unsolvable and freeze
                     if overhang_component(
                         entity,
+                        controller.ground_mask,
                         &collider,
                         transform.as_ref(),
                         &spatial_query_pipeline,
@@ -501,6 +507,7 @@ fn scaled_collider_laterally(collider: &Collider, scale: f32) -> Collider {
 
 fn overhang_component(
     entity: Entity,
+    ground_mask: LayerMask,
     collider: &Collider,
     transform: &Transform,
     spatial_query: &SpatialQueryPipeline,
@@ -515,7 +522,7 @@ fn overhang_component(
     // If there is a ledge in front of us we will hit the edge of it
     // We can use the normal of the hit to subtract off the component that is overhanging
     let cast_capsule = Collider::capsule(0.01, 0.5);
-    let filter = SpatialQueryFilter::default().with_excluded_entities([entity]);
+    let filter = SpatialQueryFilter::from_mask(ground_mask).with_excluded_entities([entity]);
     let collider_offset = collider_y_offset(collider);
     let future_position = transform.translation - collider_offset + velocity * dt;
 
@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+#[derive(Clone, Debug)]
+pub enum HistoryEntry {
+    Line {
+        speaker: Option<String>,
+        text: String,
+    },
+    /// An option the player chose.
+    Choice(String),
+}
+
+/// Everything said in the current conversation. Cleared when a new conversation starts.
+#[derive(Resource, Default)]
+pub struct DialogHistory {
+    pub entries: Vec<HistoryEntry>,
+    /// Whether the backlog window is showing.
+    pub open: bool,
+}
+
+pub fn history_egui_system(mut contexts: EguiContexts, mut history: ResMut<DialogHistory>) {
+    if !history.open {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+    let mut open = history.open;
+    egui::Window::new("History")
+        .open(&mut open)
+        .default_size([480.0, 360.0])
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical()
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for entry in history.entries.iter() {
+                        match entry {
+                            HistoryEntry::Line { speaker, text } => {
+                                if let Some(speaker) = speaker {
+                                    ui.label(egui::RichText::new(speaker).strong());
+                                }
+                                ui.label(text);
+                            }
+                            HistoryEntry::Choice(label) => {
+                                ui.label(
+                                    egui::RichText::new(format!("> {}", label))
+                                        .color(egui::Color32::YELLOW),
+                                );
+                            }
+                        }
+                        ui.add_space(4.0);
+                    }
+                });
+        });
+    history.open = open;
+}
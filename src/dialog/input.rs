@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+
+use super::history::DialogHistory;
+use super::vars::DialogVars;
+use super::{
+    available_options, choose_option, find_node, typewriter, ChoiceMessages, DialogGraph,
+    DialogState, OPTIONS_PER_PAGE,
+};
+use crate::components::Player;
+use crate::inventory::Inventory;
+
+const NUMBER_KEYS: [KeyCode; OPTIONS_PER_PAGE] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+];
+
+/// Keys that also interact in the world, so they are consumed while dialog is open.
+const CONFIRM_KEYS: [KeyCode; 2] = [KeyCode::KeyE, KeyCode::Enter];
+
+/// How far the stick has to be pushed to move the selection.
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// Keyboard and gamepad control of the open dialog.
+///
+/// Confirm (E, Enter, gamepad south) first finishes the typewriter, then picks the selected
+/// option. Number keys pick from the current page, arrows/d-pad/stick move the selection,
+/// left/right and the shoulder buttons change page, H or gamepad north toggles the history, and
+/// Escape or gamepad east closes the dialog.
+#[allow(clippy::too_many_arguments)]
+pub fn dialog_input_system(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut stick_held: Local<bool>,
+    graph: Res<DialogGraph>,
+    mut state: ResMut<DialogState>,
+    mut vars: ResMut<DialogVars>,
+    mut history: ResMut<DialogHistory>,
+    inventory_q: Query<&Inventory, With<Player>>,
+    mut messages: ChoiceMessages,
+) {
+    if !state.open {
+        return;
+    }
+    let pad = |button: GamepadButton| gamepads.iter().any(|g| g.just_pressed(button));
+    // Before the node lookup, so the dialog can always be closed.
+    if keys.just_pressed(KeyCode::Escape) || pad(GamepadButton::East) {
+        state.open = false;
+        return;
+    }
+    if state.entered != state.current {
+        return;
+    }
+    let Some(id) = state.current.as_deref() else {
+        return;
+    };
+    let Some(node) = find_node(&graph, id) else {
+        return;
+    };
+    let options = available_options(node, &vars, inventory_q.single().ok());
+    let token_count = typewriter::node_tokens(node, &vars).len();
+
+    let stick_y = gamepads
+        .iter()
+        .map(|g| g.left_stick().y)
+        .find(|y| y.abs() > STICK_THRESHOLD);
+    let stick_moved = stick_y.filter(|_| !*stick_held);
+    *stick_held = stick_y.is_some();
+
+    if keys.just_pressed(KeyCode::KeyH) || pad(GamepadButton::North) {
+        history.open = !history.open;
+    }
+
+    let page = state.selected / OPTIONS_PER_PAGE;
+    let mut pick = None;
+    if keys.any_just_pressed(CONFIRM_KEYS) || pad(GamepadButton::South) {
+        for key in CONFIRM_KEYS {
+            keys.clear_just_pressed(key);
+        }
+        pick = Some(state.selected);
+    }
+    for (i, key) in NUMBER_KEYS.iter().enumerate() {
+        if keys.just_pressed(*key) {
+            pick = Some(page * OPTIONS_PER_PAGE + i);
+        }
+    }
+    if pick.is_some() && state.revealed < token_count {
+        state.revealed = token_count;
+        return;
+    }
+    if let Some(&(index, _)) = pick.and_then(|i| options.get(i)) {
+        choose_option(node, index, &mut state, &mut vars, &mut history, &mut messages);
+        return;
+    }
+
+    if options.is_empty() {
+        return;
+    }
+    let last = options.len() - 1;
+    let up = keys.just_pressed(KeyCode::ArrowUp)
+        || pad(GamepadButton::DPadUp)
+        || stick_moved.is_some_and(|y| y > 0.0);
+    let down = keys.just_pressed(KeyCode::ArrowDown)
+        || pad(GamepadButton::DPadDown)
+        || stick_moved.is_some_and(|y| y < 0.0);
+    let prev_page = keys.just_pressed(KeyCode::ArrowLeft)
+        || pad(GamepadButton::DPadLeft)
+        || pad(GamepadButton::LeftTrigger);
+    let next_page = keys.just_pressed(KeyCode::ArrowRight)
+        || pad(GamepadButton::DPadRight)
+        || pad(GamepadButton::RightTrigger);
+    if up {
+        state.selected = if state.selected == 0 {
+            last
+        } else {
+            state.selected - 1
+        };
+    }
+    if down {
+        state.selected = if state.selected >= last {
+            0
+        } else {
+            state.selected + 1
+        };
+    }
+    if prev_page && page > 0 {
+        state.selected = (page - 1) * OPTIONS_PER_PAGE;
+    }
+    if next_page && (page + 1) * OPTIONS_PER_PAGE <= last {
+        state.selected = (page + 1) * OPTIONS_PER_PAGE;
+    }
+}
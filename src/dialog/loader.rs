@@ -0,0 +1,50 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::Deserialize;
+
+use super::validate::DialogIssue;
+use super::{DialogGraph, DialogNode};
+use crate::ron_asset::RonAsset;
+
+/// One dialog file, usually one per NPC or chapter. All files are merged into [`DialogGraph`].
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct DialogFile {
+    pub nodes: Vec<DialogNode>,
+}
+
+impl RonAsset for DialogFile {
+    const EXTENSION: &'static str = "dialog.ron";
+    const FOLDER: &'static str = "dialog";
+    type Merged = DialogGraph;
+
+    fn merge(files: &[(String, &Self)], graph: &mut DialogGraph) {
+        let (nodes, duplicates) =
+            merge_dialog_files(files.iter().map(|(path, file)| (path.clone(), *file)));
+        for issue in duplicates {
+            warn!("{}", issue);
+        }
+        info!("Dialog graph rebuilt: {} nodes", nodes.len());
+        graph.0 = nodes;
+    }
+}
+
+/// Merges dialog files into one node map. The first file, in the given order, to define an id
+/// wins; later definitions are returned as [`DialogIssue::DuplicateNode`].
+pub fn merge_dialog_files<'a>(
+    files: impl IntoIterator<Item = (String, &'a DialogFile)>,
+) -> (HashMap<String, DialogNode>, Vec<DialogIssue>) {
+    let mut nodes: HashMap<String, DialogNode> = HashMap::new();
+    let mut duplicates = Vec::new();
+    for (path, file) in files {
+        for node in file.nodes.iter() {
+            if nodes.contains_key(&node.id) {
+                duplicates.push(DialogIssue::DuplicateNode {
+                    path: path.clone(),
+                    node: node.id.clone(),
+                });
+                continue;
+            }
+            nodes.insert(node.id.clone(), node.clone());
+        }
+    }
+    (nodes, duplicates)
+}
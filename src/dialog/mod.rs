@@ -0,0 +1,710 @@
+use bevy::{
+    ecs::system::{SystemId, SystemParam},
+    platform::collections::HashMap,
+    prelude::*,
+};
+use bevy_egui::{egui, EguiContexts, EguiPrimaryContextPass, EguiTextureHandle};
+use serde::Deserialize;
+
+use crate::components::Player;
+use crate::interact::{Interact, InteractAppExt, Verb};
+use crate::inventory::Inventory;
+use crate::ron_asset::RonAssetAppExt;
+
+pub mod history;
+mod input;
+pub mod loader;
+pub mod typewriter;
+pub mod validate;
+pub mod vars;
+
+use history::{DialogHistory, HistoryEntry};
+use typewriter::DEFAULT_TYPING_SPEED;
+use vars::{Condition, DialogEffect, DialogVars};
+
+/// Node dialog starts on when nothing else is given.
+pub const ROOT_NODE: &str = "root";
+
+/// Overrides [`Npc::dialogue_id`], e.g. from level scripts.
+#[derive(Component)]
+pub struct DialogStart(pub String);
+
+/// Keyboard and gamepad handling of open dialog. It consumes the keys it uses, so systems that
+/// read the same keys should run after it.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DialogInputSet;
+
+#[derive(Resource)]
+pub struct DialogState {
+    pub open: bool,
+    pub current: Option<String>,
+    /// The NPC being talked to, if any.
+    pub npc: Option<Entity>,
+    /// Typewriter tokens of the current text shown so far, see [`typewriter::parse_markup`].
+    pub revealed: usize,
+    /// Seconds banked towards revealing the next token.
+    pub reveal_timer: f32,
+    /// Graphemes per second, unless the NPC sets its own.
+    pub typing_speed: f32,
+    /// Index into the node's available options.
+    pub selected: usize,
+    /// The node `current` resolved to once its conditions were checked.
+    entered: Option<String>,
+}
+
+impl Default for DialogState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            current: None,
+            npc: None,
+            revealed: 0,
+            reveal_timer: 0.0,
+            typing_speed: DEFAULT_TYPING_SPEED,
+            selected: 0,
+            entered: None,
+        }
+    }
+}
+
+impl DialogState {
+    /// Opens the dialog on `node`, talking to `npc`.
+    pub fn start(&mut self, node: &str, npc: Option<Entity>) {
+        self.open = true;
+        self.current = Some(node.to_string());
+        self.npc = npc;
+        self.reset_typing();
+        self.selected = 0;
+    }
+
+    fn reset_typing(&mut self) {
+        self.revealed = 0;
+        self.reveal_timer = 0.0;
+    }
+}
+
+/// A conversation opened on `node`, after fallbacks.
+#[derive(Message, Clone, Debug)]
+pub struct DialogStarted {
+    pub npc: Option<Entity>,
+    pub node: String,
+}
+
+/// The player reached `node`, including the first node of a conversation.
+#[derive(Message, Clone, Debug)]
+pub struct NodeEntered {
+    pub npc: Option<Entity>,
+    pub node: String,
+}
+
+#[derive(Message, Clone, Debug)]
+pub struct OptionChosen {
+    pub npc: Option<Entity>,
+    pub node: String,
+    /// Index into the node's `options`.
+    pub option: usize,
+    pub label: String,
+}
+
+/// The conversation closed. `node` is the last node shown.
+#[derive(Message, Clone, Debug)]
+pub struct DialogEnded {
+    pub npc: Option<Entity>,
+    pub node: String,
+}
+
+/// Every dialog node, by id.
+#[derive(Resource, Default)]
+pub struct DialogGraph(pub HashMap<String, DialogNode>);
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DialogOption {
+    pub label: String,
+    #[serde(default)]
+    pub next: Option<String>,
+    /// Written as `"name arg1 arg2"` in dialog files.
+    #[serde(default)]
+    pub actions: Vec<DialogAction>,
+    /// The option is hidden unless all of these hold.
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    #[serde(default)]
+    pub effects: Vec<DialogEffect>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DialogNode {
+    pub id: String,
+    /// Name shown above the text. Defaults to the NPC's name.
+    #[serde(default)]
+    pub speaker: Option<String>,
+    /// Image path under `assets/`. Defaults to the NPC's portrait unless `speaker` is set.
+    #[serde(default)]
+    pub portrait: Option<String>,
+    /// `{name}` is replaced with the value of dialog variable `name`.
+    pub text: String,
+    #[serde(default)]
+    pub options: Vec<DialogOption>,
+    /// If any of these fail when the node is entered, dialog goes to `fallback`, or closes
+    /// if there is none.
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    #[serde(default)]
+    pub fallback: Option<String>,
+    /// Dialog starts on this node from somewhere outside the graph, usually an NPC's
+    /// `dialogue_id`. Only used by validation.
+    #[serde(default)]
+    pub entry: bool,
+}
+
+/// A named action run when a dialog option is chosen, e.g. `give_item key_red`.
+///
+/// Sent as a message and dispatched to the handler registered under `name`.
+#[derive(Message, Clone, Deserialize, Debug)]
+#[serde(try_from = "String")]
+pub struct DialogAction {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl DialogAction {
+    /// Parses `"name arg1 arg2"`. Returns `None` for an empty string.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut words = s.split_whitespace();
+        let name = words.next()?.to_string();
+        Some(Self {
+            name,
+            args: words.map(str::to_string).collect(),
+        })
+    }
+}
+
+impl TryFrom<String> for DialogAction {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s).ok_or_else(|| "empty dialog action".to_string())
+    }
+}
+
+/// Handlers for [`DialogAction`]s by name. Each handler is a one-shot system taking the
+/// action's arguments.
+#[derive(Resource, Default)]
+pub struct DialogActions(HashMap<String, SystemId<In<Vec<String>>>>);
+
+impl DialogActions {
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+}
+
+pub trait DialogActionsExt {
+    /// Registers `handler` to run whenever a dialog option with action `name` is chosen.
+    fn register_dialog_action<M>(
+        &mut self,
+        name: &str,
+        handler: impl IntoSystem<In<Vec<String>>, (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl DialogActionsExt for App {
+    fn register_dialog_action<M>(
+        &mut self,
+        name: &str,
+        handler: impl IntoSystem<In<Vec<String>>, (), M> + 'static,
+    ) -> &mut Self {
+        let id = self.register_system(handler);
+        self.world_mut()
+            .get_resource_or_init::<DialogActions>()
+            .0
+            .insert(name.to_string(), id);
+        self
+    }
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct Npc {
+    pub dialogue_id: String,
+    /// Shown as the speaker of the NPC's lines.
+    pub name: String,
+    /// Image path under `assets/`, empty for none.
+    pub portrait: String,
+    /// Graphemes per second for this NPC's lines. 0 uses [`DialogState::typing_speed`].
+    pub typing_speed: f32,
+}
+
+/// Options shown at once; the rest are on further pages.
+pub const OPTIONS_PER_PAGE: usize = 4;
+
+fn find_node<'a>(graph: &'a DialogGraph, id: &str) -> Option<&'a DialogNode> {
+    graph.0.get(id)
+}
+
+/// Who says a node's text.
+struct Speaker<'a> {
+    name: Option<&'a str>,
+    portrait: Option<&'a str>,
+}
+
+impl<'a> Speaker<'a> {
+    /// The node's own speaker if it has one, otherwise the NPC being talked to.
+    fn of(node: &'a DialogNode, npc: Option<&'a Npc>) -> Self {
+        let non_empty = |s: &'a String| Some(s.as_str()).filter(|s| !s.is_empty());
+        match &node.speaker {
+            Some(name) => Speaker {
+                name: Some(name.as_str()),
+                portrait: node.portrait.as_deref(),
+            },
+            None => Speaker {
+                name: npc.and_then(|n| non_empty(&n.name)),
+                portrait: node
+                    .portrait
+                    .as_deref()
+                    .or_else(|| npc.and_then(|n| non_empty(&n.portrait))),
+            },
+        }
+    }
+}
+
+/// The node's options whose conditions hold, with their index in `node.options`.
+fn available_options<'a>(
+    node: &'a DialogNode,
+    vars: &DialogVars,
+    inventory: Option<&Inventory>,
+) -> Vec<(usize, &'a DialogOption)> {
+    node.options
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| vars.check_all(&o.conditions, inventory))
+        .collect()
+}
+
+/// Messages sent when an option is chosen.
+#[derive(SystemParam)]
+struct ChoiceMessages<'w> {
+    actions: MessageWriter<'w, DialogAction>,
+    chosen: MessageWriter<'w, OptionChosen>,
+}
+
+/// Applies the option's effects, sends its actions and moves on to `next`, or closes the dialog.
+fn choose_option(
+    node: &DialogNode,
+    index: usize,
+    state: &mut DialogState,
+    vars: &mut DialogVars,
+    history: &mut DialogHistory,
+    messages: &mut ChoiceMessages,
+) {
+    let opt = &node.options[index];
+    history.entries.push(HistoryEntry::Choice(opt.label.clone()));
+    for effect in opt.effects.iter() {
+        effect.apply(vars);
+    }
+    messages.chosen.write(OptionChosen {
+        npc: state.npc,
+        node: node.id.clone(),
+        option: index,
+        label: opt.label.clone(),
+    });
+    messages.actions.write_batch(opt.actions.iter().cloned());
+    if let Some(next) = &opt.next {
+        state.current = Some(next.clone());
+        state.reset_typing();
+    } else {
+        state.open = false;
+    }
+    state.selected = 0;
+}
+
+/// Opens the NPC's dialog: its `DialogStart` if it has one, otherwise its `dialogue_id`, otherwise
+/// the root node.
+fn talk_to_npc(
+    In(interact): In<Interact>,
+    npc_q: Query<(&Npc, Option<&DialogStart>)>,
+    mut state: ResMut<DialogState>,
+) {
+    let Ok((npc, start)) = npc_q.get(interact.target) else {
+        return;
+    };
+    let node = match start {
+        Some(start) => start.0.as_str(),
+        None if !npc.dialogue_id.is_empty() => npc.dialogue_id.as_str(),
+        None => ROOT_NODE,
+    };
+    state.start(node, Some(interact.target));
+}
+
+/// Lifecycle messages, see [`enter_node_system`].
+#[derive(SystemParam)]
+struct LifecycleMessages<'w> {
+    started: MessageWriter<'w, DialogStarted>,
+    entered: MessageWriter<'w, NodeEntered>,
+    ended: MessageWriter<'w, DialogEnded>,
+}
+
+/// Follows node fallbacks from `current`, records the node the player ends up on as visited and
+/// adds its line to the history. Every way of opening, moving through or closing dialog passes
+/// through here, so this is also where the lifecycle messages are sent.
+fn enter_node_system(
+    mut state: ResMut<DialogState>,
+    graph: Res<DialogGraph>,
+    mut vars: ResMut<DialogVars>,
+    mut history: ResMut<DialogHistory>,
+    inventory_q: Query<&Inventory, With<Player>>,
+    npc_q: Query<&Npc>,
+    mut messages: LifecycleMessages,
+) {
+    if !state.open {
+        if let Some(node) = state.entered.take() {
+            messages.ended.write(DialogEnded {
+                npc: state.npc,
+                node,
+            });
+        }
+        history.open = false;
+        return;
+    }
+    if state.entered == state.current {
+        return;
+    }
+    let starting = state.entered.is_none();
+    if starting {
+        history.entries.clear();
+    }
+    let inventory = inventory_q.single().ok();
+    let mut id = state.current.clone();
+    let mut entered = None;
+    // Bounded so a fallback cycle can't hang the game.
+    for _ in 0..=graph.0.len() {
+        let Some(node) = id.as_deref().and_then(|id| find_node(&graph, id)) else {
+            break;
+        };
+        if vars.check_all(&node.conditions, inventory) {
+            vars.visited.insert(node.id.clone());
+            let npc = state.npc.and_then(|e| npc_q.get(e).ok());
+            history.entries.push(HistoryEntry::Line {
+                speaker: Speaker::of(node, npc).name.map(str::to_string),
+                text: typewriter::plain_text(&typewriter::node_tokens(node, &vars)),
+            });
+            entered = Some(node.id.clone());
+            break;
+        }
+        id = node.fallback.clone();
+    }
+    let Some(node) = entered else {
+        // The id is unknown, or neither it nor its fallbacks pass their conditions. `entered`
+        // is left alone, so a conversation that was under way is reported as ended next frame.
+        warn!("no dialog node to enter from {:?}", state.current);
+        state.open = false;
+        return;
+    };
+    if starting {
+        messages.started.write(DialogStarted {
+            npc: state.npc,
+            node: node.clone(),
+        });
+    }
+    messages.entered.write(NodeEntered {
+        npc: state.npc,
+        node: node.clone(),
+    });
+    state.current = Some(node.clone());
+    state.entered = Some(node);
+    state.reset_typing();
+    state.selected = 0;
+}
+
+fn dialog_typing_system(
+    time: Res<Time>,
+    mut state: ResMut<DialogState>,
+    graph: Res<DialogGraph>,
+    vars: Res<DialogVars>,
+    npc_q: Query<&Npc>,
+) {
+    if !state.open {
+        return;
+    }
+    let Some(id) = state.current.as_deref() else { return; };
+    let Some(node) = find_node(&graph, id) else { return; };
+    let tokens = typewriter::node_tokens(node, &vars);
+    let speed = state
+        .npc
+        .and_then(|e| npc_q.get(e).ok())
+        .map(|npc| npc.typing_speed)
+        .filter(|s| *s > 0.0)
+        .unwrap_or(state.typing_speed);
+
+    state.reveal_timer += time.delta_secs();
+    while let Some(token) = tokens.get(state.revealed) {
+        let duration = token.duration(speed);
+        if state.reveal_timer < duration {
+            break;
+        }
+        state.reveal_timer -= duration;
+        state.revealed += 1;
+    }
+    if state.revealed >= tokens.len() {
+        state.reveal_timer = 0.0;
+    }
+}
+
+pub(crate) fn run_dialog_actions_system(
+    mut commands: Commands,
+    mut actions: MessageReader<DialogAction>,
+    registry: Res<DialogActions>,
+) {
+    for action in actions.read() {
+        match registry.0.get(&action.name) {
+            Some(&id) => commands.run_system_with(id, action.args.clone()),
+            None => warn!("Unknown dialog action: {}", action.name),
+        }
+    }
+}
+
+/// Reports actions in the graph that no handler was registered for.
+fn report_unknown_actions_system(graph: Res<DialogGraph>, registry: Res<DialogActions>) {
+    for node in graph.0.values() {
+        for action in node.options.iter().flat_map(|o| o.actions.iter()) {
+            if !registry.contains(&action.name) {
+                warn!(
+                    "Dialog node {}: unknown action {:?}",
+                    node.id, action.name
+                );
+            }
+        }
+    }
+}
+
+/// `teleport x y z`: moves the player.
+fn teleport_action(In(args): In<Vec<String>>, mut player_q: Query<&mut Transform, With<Player>>) {
+    let coords: Vec<f32> = args.iter().filter_map(|a| a.parse().ok()).collect();
+    let [x, y, z] = coords[..] else {
+        warn!("teleport expects 3 numbers, got {:?}", args);
+        return;
+    };
+    if let Ok(mut t) = player_q.single_mut() {
+        t.translation = Vec3::new(x, y, z);
+    }
+}
+
+/// Egui textures for speaker portraits, by image path.
+#[derive(Resource, Default)]
+struct DialogPortraits(HashMap<String, egui::TextureId>);
+
+#[allow(clippy::too_many_arguments)]
+fn dialog_egui_system(
+    mut contexts: EguiContexts,
+    graph: Res<DialogGraph>,
+    mut state: ResMut<DialogState>,
+    mut vars: ResMut<DialogVars>,
+    mut history: ResMut<DialogHistory>,
+    inventory_q: Query<&Inventory, With<Player>>,
+    npc_q: Query<&Npc>,
+    asset_server: Res<AssetServer>,
+    mut portraits: ResMut<DialogPortraits>,
+    mut messages: ChoiceMessages,
+) {
+    if !state.open || state.entered != state.current {
+        return;
+    }
+    let Some(id) = state.current.as_deref() else { return; };
+    let Some(node) = find_node(&graph, id) else { return; };
+    let speaker = Speaker::of(node, state.npc.and_then(|e| npc_q.get(e).ok()));
+    let portrait = speaker.portrait.map(|path| {
+        *portraits.0.entry(path.to_string()).or_insert_with(|| {
+            contexts.add_image(EguiTextureHandle::Strong(asset_server.load(path.to_string())))
+        })
+    });
+    let ctx_res = contexts.ctx_mut();
+    let ctx = match ctx_res {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    let inventory = inventory_q.single().ok();
+    let tokens = typewriter::node_tokens(node, &vars);
+    let revealed = state.revealed;
+    let available = available_options(node, &vars, inventory);
+    let pages = available.len().div_ceil(OPTIONS_PER_PAGE).max(1);
+    let page = state.selected / OPTIONS_PER_PAGE;
+    let mut selected = state.selected;
+    let mut clicked = None;
+    egui::TopBottomPanel::bottom("dialog_panel")
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.set_height_range(egui::Rangef::new(180.0, 300.0));
+            ui.visuals_mut().override_text_color = Some(egui::Color32::WHITE);
+            ui.horizontal_top(|ui| {
+                if let Some(portrait) = portrait {
+                    ui.image(egui::load::SizedTexture::new(portrait, [96.0, 96.0]));
+                }
+                ui.vertical(|ui| {
+                    if let Some(name) = speaker.name {
+                        ui.label(
+                            egui::RichText::new(name)
+                                .color(egui::Color32::LIGHT_BLUE)
+                                .size(18.0)
+                                .strong(),
+                        );
+                    }
+                    typewriter::show_tokens(ui, &tokens, revealed, 20.0);
+                });
+            });
+            ui.add_space(6.0);
+            let shown = available
+                .iter()
+                .enumerate()
+                .skip(page * OPTIONS_PER_PAGE)
+                .take(OPTIONS_PER_PAGE);
+            for (i, (_, opt)) in shown {
+                let label = egui::RichText::new(format!(
+                    "{}. {}",
+                    i % OPTIONS_PER_PAGE + 1,
+                    opt.label
+                ))
+                .color(egui::Color32::YELLOW)
+                .size(18.0);
+                let response = ui.selectable_label(i == selected, label);
+                if response.hovered() {
+                    selected = i;
+                }
+                if response.clicked() {
+                    clicked = Some(i);
+                }
+            }
+            if pages > 1 {
+                ui.horizontal(|ui| {
+                    if ui.button("<").clicked() && page > 0 {
+                        selected = (page - 1) * OPTIONS_PER_PAGE;
+                    }
+                    ui.label(format!("{}/{}", page + 1, pages));
+                    if ui.button(">").clicked() && page + 1 < pages {
+                        selected = (page + 1) * OPTIONS_PER_PAGE;
+                    }
+                });
+            }
+        });
+    state.selected = selected;
+    if let Some(&(index, _)) = clicked.and_then(|i| available.get(i)) {
+        choose_option(node, index, &mut state, &mut vars, &mut history, &mut messages);
+    }
+}
+
+pub struct DialogPlugin;
+
+impl Plugin for DialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DialogState>()
+            .init_resource::<DialogGraph>()
+            .init_resource::<DialogVars>()
+            .init_resource::<DialogHistory>()
+            .init_resource::<DialogPortraits>()
+            .init_resource::<DialogActions>()
+            .add_message::<DialogAction>()
+            .add_message::<DialogStarted>()
+            .add_message::<NodeEntered>()
+            .add_message::<OptionChosen>()
+            .add_message::<DialogEnded>()
+            .register_dialog_action("teleport", teleport_action)
+            .register_interaction::<Npc>(Verb::new("talk", "Talk"), talk_to_npc)
+            .add_systems(
+                Update,
+                (
+                    input::dialog_input_system.in_set(DialogInputSet),
+                    enter_node_system,
+                    dialog_typing_system,
+                )
+                    .chain(),
+            )
+            .add_systems(Update, run_dialog_actions_system)
+            .add_systems(
+                Update,
+                report_unknown_actions_system.run_if(resource_changed::<DialogGraph>),
+            )
+            .add_systems(Update, validate::validate_dialog_system)
+            .add_systems(
+                EguiPrimaryContextPass,
+                (dialog_egui_system, history::history_egui_system),
+            )
+            .init_ron_asset::<loader::DialogFile>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ron_asset::parse_ron;
+
+    fn app_with_graph(ron: &str) -> App {
+        let file: loader::DialogFile = parse_ron(ron.as_bytes(), "test.dialog.ron").unwrap();
+        let (nodes, _) = loader::merge_dialog_files([("test.dialog.ron".to_string(), &file)]);
+        let mut app = App::new();
+        app.init_resource::<DialogState>()
+            .insert_resource(DialogGraph(nodes))
+            .init_resource::<DialogVars>()
+            .init_resource::<DialogHistory>()
+            .add_message::<DialogStarted>()
+            .add_message::<NodeEntered>()
+            .add_message::<DialogEnded>()
+            .add_systems(Update, enter_node_system);
+        app
+    }
+
+    fn start(app: &mut App, node: &str) {
+        app.world_mut()
+            .resource_mut::<DialogState>()
+            .start(node, None);
+        app.update();
+    }
+
+    fn started(app: &App) -> usize {
+        app.world()
+            .resource::<Messages<DialogStarted>>()
+            .iter_current_update_messages()
+            .count()
+    }
+
+    #[test]
+    fn enters_the_start_node() {
+        let mut app =
+            app_with_graph(r#"(nodes: [(id: "root", text: "Hi", options: [(label: "Bye")])])"#);
+        start(&mut app, "root");
+        let state = app.world().resource::<DialogState>();
+        assert!(state.open);
+        assert_eq!(state.entered.as_deref(), Some("root"));
+        assert_eq!(started(&app), 1);
+    }
+
+    #[test]
+    fn unknown_start_node_closes_the_dialog() {
+        let mut app =
+            app_with_graph(r#"(nodes: [(id: "root", text: "Hi", options: [(label: "Bye")])])"#);
+        start(&mut app, "nowhere");
+        let state = app.world().resource::<DialogState>();
+        assert!(!state.open);
+        assert_eq!(state.entered, None);
+        assert_eq!(started(&app), 0);
+    }
+
+    #[test]
+    fn fallback_cycle_closes_the_dialog() {
+        let mut app = app_with_graph(
+            r#"(nodes: [
+                (id: "a", text: "A", conditions: [Flag("never")], fallback: "b", options: [(label: "Bye")]),
+                (id: "b", text: "B", conditions: [Flag("never")], fallback: "a", options: [(label: "Bye")]),
+            ])"#,
+        );
+        start(&mut app, "a");
+        let state = app.world().resource::<DialogState>();
+        assert!(!state.open);
+        assert_eq!(state.entered, None);
+        assert_eq!(started(&app), 0);
+        assert_eq!(
+            app.world()
+                .resource::<Messages<NodeEntered>>()
+                .iter_current_update_messages()
+                .count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use bevy_egui::egui;
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::vars::DialogVars;
+use super::DialogNode;
+
+/// Graphemes per second when neither the speaker nor the settings say otherwise.
+pub const DEFAULT_TYPING_SPEED: f32 = 40.0;
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct GlyphStyle {
+    pub color: Option<egui::Color32>,
+    /// Drawn as egui "strong" text.
+    pub bold: bool,
+    pub shake: bool,
+}
+
+/// One step of the typewriter.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Token {
+    /// A single grapheme cluster. `speed` multiplies the typing speed while it is typed.
+    Grapheme {
+        text: String,
+        style: GlyphStyle,
+        speed: f32,
+    },
+    /// A pause, in seconds.
+    Wait(f32),
+}
+
+impl Token {
+    /// Seconds it takes to type this token at `typing_speed` graphemes per second.
+    pub fn duration(&self, typing_speed: f32) -> f32 {
+        match self {
+            Token::Grapheme { speed, .. } => 1.0 / (typing_speed * speed).max(0.01),
+            Token::Wait(secs) => *secs,
+        }
+    }
+}
+
+/// The node's text with variables filled in, split into typewriter tokens.
+pub fn node_tokens(node: &DialogNode, vars: &DialogVars) -> Vec<Token> {
+    parse_markup(&vars.interpolate(&node.text))
+}
+
+/// The tokens' text without markup.
+pub fn plain_text(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Grapheme { text, .. } => Some(text.as_str()),
+            Token::Wait(_) => None,
+        })
+        .collect()
+}
+
+/// Splits dialog text into graphemes, applying inline markup:
+///
+/// - `[color=red]..[/color]`, also `#rrggbb`
+/// - `[b]..[/b]`
+/// - `[shake]..[/shake]`
+/// - `[speed=0.5]..[/speed]`, a multiplier on the typing speed
+/// - `[wait=0.4]`, a pause in seconds, which must not be negative
+///
+/// `[[` is a literal `[`. Tags that aren't recognised are shown as written.
+pub fn parse_markup(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut colors: Vec<egui::Color32> = Vec::new();
+    let mut speeds: Vec<f32> = Vec::new();
+    let mut bold = 0;
+    let mut shake = 0;
+
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut escaped = false;
+        let (plain, tag) = match rest.find('[') {
+            Some(start) if rest[start..].starts_with("[[") => {
+                escaped = true;
+                (&rest[..start + 1], None)
+            }
+            Some(start) => match rest[start..].find(']') {
+                Some(end) => (&rest[..start], Some(&rest[start..start + end + 1])),
+                None => (rest, None),
+            },
+            None => (rest, None),
+        };
+        let style = GlyphStyle {
+            color: colors.last().copied(),
+            bold: bold > 0,
+            shake: shake > 0,
+        };
+        let speed = speeds.last().copied().unwrap_or(1.0);
+        tokens.extend(plain.graphemes(true).map(|g| Token::Grapheme {
+            text: g.to_string(),
+            style,
+            speed,
+        }));
+        rest = &rest[plain.len()..];
+        if escaped {
+            rest = &rest[1..];
+            continue;
+        }
+        let Some(tag) = tag else {
+            continue;
+        };
+        rest = &rest[tag.len()..];
+
+        let inner = &tag[1..tag.len() - 1];
+        let (name, value) = match inner.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (inner.trim(), None),
+        };
+        let known = match (name, value) {
+            ("color", Some(value)) => parse_color(value).map(|c| colors.push(c)).is_some(),
+            ("/color", None) => colors.pop().is_some(),
+            ("b", None) => {
+                bold += 1;
+                true
+            }
+            ("/b", None) if bold > 0 => {
+                bold -= 1;
+                true
+            }
+            ("shake", None) => {
+                shake += 1;
+                true
+            }
+            ("/shake", None) if shake > 0 => {
+                shake -= 1;
+                true
+            }
+            ("speed", Some(value)) => value.parse().map(|s: f32| speeds.push(s.max(0.01))).is_ok(),
+            ("/speed", None) => speeds.pop().is_some(),
+            // Negative, infinite or NaN waits would stall or skip the typewriter.
+            ("wait", Some(value)) => match value.parse::<f32>() {
+                Ok(secs) if secs.is_finite() && secs >= 0.0 => {
+                    tokens.push(Token::Wait(secs));
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        if !known {
+            tokens.extend(tag.graphemes(true).map(|g| Token::Grapheme {
+                text: g.to_string(),
+                style,
+                speed,
+            }));
+        }
+    }
+    tokens
+}
+
+fn parse_color(value: &str) -> Option<egui::Color32> {
+    Some(match value {
+        "red" => egui::Color32::RED,
+        "green" => egui::Color32::GREEN,
+        "blue" => egui::Color32::LIGHT_BLUE,
+        "yellow" => egui::Color32::YELLOW,
+        "orange" => egui::Color32::ORANGE,
+        "gold" => egui::Color32::GOLD,
+        "gray" | "grey" => egui::Color32::GRAY,
+        "white" => egui::Color32::WHITE,
+        hex => return egui::Color32::from_hex(hex).ok(),
+    })
+}
+
+/// Draws the first `revealed` tokens as wrapped text.
+pub fn show_tokens(ui: &mut egui::Ui, tokens: &[Token], revealed: usize, size: f32) {
+    let time = ui.input(|i| i.time) as f32;
+    let default_color = ui.visuals().text_color();
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing = egui::vec2(0.0, 0.0);
+        let mut run = String::new();
+        let mut run_style = GlyphStyle::default();
+        let flush = |ui: &mut egui::Ui, run: &mut String, style: GlyphStyle| {
+            if run.is_empty() {
+                return;
+            }
+            ui.label(rich_text(std::mem::take(run), style, size));
+        };
+        let graphemes = tokens.iter().take(revealed).filter_map(|t| match t {
+            Token::Grapheme { text, style, .. } => Some((text, *style)),
+            Token::Wait(_) => None,
+        });
+        for (i, (text, style)) in graphemes.enumerate() {
+            if style != run_style {
+                flush(ui, &mut run, run_style);
+                run_style = style;
+            }
+            if !style.shake {
+                run.push_str(text);
+                continue;
+            }
+            let galley = ui.painter().layout_no_wrap(
+                text.clone(),
+                egui::FontId::proportional(size),
+                style.color.unwrap_or(default_color),
+            );
+            let (rect, _) = ui.allocate_exact_size(galley.size(), egui::Sense::hover());
+            let phase = time * 30.0 + i as f32 * 1.7;
+            let offset = egui::vec2(phase.sin(), (phase * 1.3).cos()) * 1.5;
+            ui.painter()
+                .galley(rect.min + offset, galley, default_color);
+        }
+        flush(ui, &mut run, run_style);
+    });
+}
+
+fn rich_text(text: String, style: GlyphStyle, size: f32) -> egui::RichText {
+    let mut rich = egui::RichText::new(text).size(size);
+    if let Some(color) = style.color {
+        rich = rich.color(color);
+    }
+    if style.bold {
+        rich = rich.strong();
+    }
+    rich
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_is_a_pause() {
+        assert_eq!(parse_markup("[wait=0.5]"), vec![Token::Wait(0.5)]);
+    }
+
+    #[test]
+    fn bad_waits_are_shown_as_written() {
+        for text in ["[wait=-1]", "[wait=inf]", "[wait=NaN]", "[wait=soon]"] {
+            assert_eq!(plain_text(&parse_markup(text)), text);
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use std::{fmt, fs, path::Path};
+
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+use super::loader::{merge_dialog_files, DialogFile};
+use super::{DialogGraph, DialogNode, DialogStart, Npc, ROOT_NODE};
+use crate::ron_asset::{parse_ron, RonAsset};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DialogIssue {
+    /// A node id defined in more than one file. Only the first definition is used.
+    DuplicateNode {
+        path: String,
+        node: String,
+    },
+    MissingRoot,
+    /// An option's `next` names a node that does not exist.
+    DanglingNext {
+        node: String,
+        target: String,
+    },
+    DanglingFallback {
+        node: String,
+        target: String,
+    },
+    /// No path leads to the node from `root`, an entry node or an NPC.
+    Unreachable {
+        node: String,
+    },
+    /// The node has no options, so the dialog can never be closed from it.
+    DeadEnd {
+        node: String,
+    },
+    MissingNpcTarget {
+        npc: String,
+        target: String,
+    },
+}
+
+impl DialogIssue {
+    /// Errors break dialog at runtime; the rest are probably mistakes.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, DialogIssue::Unreachable { .. })
+    }
+}
+
+impl fmt::Display for DialogIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DialogIssue::DuplicateNode { path, node } => write!(
+                f,
+                "{}: dialog node {:?} is already defined in another file",
+                path, node
+            ),
+            DialogIssue::MissingRoot => write!(f, "there is no {:?} dialog node", ROOT_NODE),
+            DialogIssue::DanglingNext { node, target } => {
+                write!(
+                    f,
+                    "dialog node {:?}: option leads to unknown node {:?}",
+                    node, target
+                )
+            }
+            DialogIssue::DanglingFallback { node, target } => {
+                write!(
+                    f,
+                    "dialog node {:?}: unknown fallback node {:?}",
+                    node, target
+                )
+            }
+            DialogIssue::Unreachable { node } => {
+                write!(f, "dialog node {:?} can never be reached", node)
+            }
+            DialogIssue::DeadEnd { node } => {
+                write!(
+                    f,
+                    "dialog node {:?} has no options, so it can't be left",
+                    node
+                )
+            }
+            DialogIssue::MissingNpcTarget { npc, target } => {
+                write!(f, "NPC {} starts at unknown dialog node {:?}", npc, target)
+            }
+        }
+    }
+}
+
+/// Checks the graph for broken links and nodes the player can get stuck on or never see.
+///
+/// `npc_targets` are `(npc name, node id)` pairs for every NPC that starts a dialog.
+pub fn validate_graph(
+    nodes: &HashMap<String, DialogNode>,
+    npc_targets: &[(String, String)],
+) -> Vec<DialogIssue> {
+    let mut issues = Vec::new();
+    if !nodes.contains_key(ROOT_NODE) {
+        issues.push(DialogIssue::MissingRoot);
+    }
+
+    let mut ids: Vec<&String> = nodes.keys().collect();
+    ids.sort();
+    for id in ids.iter() {
+        let node = &nodes[*id];
+        for next in node.options.iter().filter_map(|o| o.next.as_ref()) {
+            if !nodes.contains_key(next) {
+                issues.push(DialogIssue::DanglingNext {
+                    node: node.id.clone(),
+                    target: next.clone(),
+                });
+            }
+        }
+        if let Some(fallback) = &node.fallback {
+            if !nodes.contains_key(fallback) {
+                issues.push(DialogIssue::DanglingFallback {
+                    node: node.id.clone(),
+                    target: fallback.clone(),
+                });
+            }
+        }
+        if node.options.is_empty() {
+            issues.push(DialogIssue::DeadEnd {
+                node: node.id.clone(),
+            });
+        }
+    }
+
+    for (npc, target) in npc_targets.iter() {
+        if !nodes.contains_key(target) {
+            issues.push(DialogIssue::MissingNpcTarget {
+                npc: npc.clone(),
+                target: target.clone(),
+            });
+        }
+    }
+
+    let mut reached: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = std::iter::once(ROOT_NODE)
+        .chain(nodes.values().filter(|n| n.entry).map(|n| n.id.as_str()))
+        .chain(npc_targets.iter().map(|(_, target)| target.as_str()))
+        .collect();
+    while let Some(id) = stack.pop() {
+        let Some(node) = nodes.get(id) else {
+            continue;
+        };
+        if !reached.insert(id) {
+            continue;
+        }
+        stack.extend(node.options.iter().filter_map(|o| o.next.as_deref()));
+        stack.extend(node.fallback.as_deref());
+    }
+    for id in ids.iter() {
+        if !reached.contains(id.as_str()) {
+            issues.push(DialogIssue::Unreachable {
+                node: (*id).clone(),
+            });
+        }
+    }
+    issues
+}
+
+fn npc_name(entity: Entity, name: Option<&Name>) -> String {
+    match name {
+        Some(name) => format!("{:?}", name.as_str()),
+        None => format!("{}", entity),
+    }
+}
+
+/// Every node an NPC can start dialog on.
+fn npc_targets(npc: &Npc, start: Option<&DialogStart>) -> Vec<String> {
+    let mut targets: Vec<String> = start.map(|s| s.0.clone()).into_iter().collect();
+    if !npc.dialogue_id.is_empty() {
+        targets.push(npc.dialogue_id.clone());
+    }
+    targets
+}
+
+/// Validates the whole graph when it is rebuilt, and the targets of NPCs as they spawn.
+#[allow(clippy::type_complexity)]
+pub fn validate_dialog_system(
+    graph: Res<DialogGraph>,
+    npc_q: Query<(Entity, Option<&Name>, Ref<Npc>, Option<&DialogStart>)>,
+) {
+    if graph.0.is_empty() {
+        return;
+    }
+    if graph.is_changed() {
+        let targets: Vec<(String, String)> = npc_q
+            .iter()
+            .flat_map(|(e, name, npc, start)| {
+                npc_targets(&npc, start)
+                    .into_iter()
+                    .map(move |target| (npc_name(e, name), target))
+            })
+            .collect();
+        for issue in validate_graph(&graph.0, &targets) {
+            if issue.is_error() {
+                error!("{}", issue);
+            } else {
+                warn!("{}", issue);
+            }
+        }
+        return;
+    }
+    for (e, name, npc, start) in npc_q.iter() {
+        if !npc.is_added() {
+            continue;
+        }
+        for target in npc_targets(&npc, start) {
+            if !graph.0.contains_key(&target) {
+                error!(
+                    "{}",
+                    DialogIssue::MissingNpcTarget {
+                        npc: npc_name(e, name),
+                        target,
+                    }
+                );
+            }
+        }
+    }
+}
+
+/// Loads every dialog file under `dir` without starting the game, prints any issues and
+/// returns the process exit code: 1 if there were errors or a file failed to parse.
+///
+/// NPCs live in Blender scenes, so their targets are not checked here; mark nodes that NPCs
+/// start on with `entry: true` to keep them from being reported as unreachable.
+pub fn validate_dialog_cli(dir: Option<&Path>) -> i32 {
+    let default_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join(DialogFile::FOLDER);
+    let dir = dir.unwrap_or(&default_dir);
+
+    let mut paths = Vec::new();
+    collect_dialog_paths(dir, &mut paths);
+    paths.sort();
+
+    let mut failed = false;
+    let mut files = Vec::new();
+    for path in paths.iter() {
+        let display = path.display().to_string();
+        let parsed = fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| {
+                parse_ron::<DialogFile>(&bytes, &display).map_err(|err| err.to_string())
+            });
+        match parsed {
+            Ok(file) => files.push((display, file)),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                failed = true;
+            }
+        }
+    }
+
+    let (nodes, mut issues) =
+        merge_dialog_files(files.iter().map(|(path, file)| (path.clone(), file)));
+    issues.extend(validate_graph(&nodes, &[]));
+    for issue in issues.iter() {
+        let level = if issue.is_error() { "error" } else { "warning" };
+        eprintln!("{}: {}", level, issue);
+    }
+    failed |= issues.iter().any(DialogIssue::is_error);
+
+    println!(
+        "Checked {} dialog files, {} nodes: {} issues",
+        files.len(),
+        nodes.len(),
+        issues.len()
+    );
+    if failed {
+        1
+    } else {
+        0
+    }
+}
+
+fn collect_dialog_paths(dir: &Path, out: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        eprintln!("error: can't read {}", dir.display());
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_dialog_paths(&path, out);
+        } else if path.to_string_lossy().ends_with(".dialog.ron") {
+            out.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(ron: &str) -> HashMap<String, DialogNode> {
+        let file: DialogFile = parse_ron(ron.as_bytes(), "test.dialog.ron").unwrap();
+        let (nodes, duplicates) = merge_dialog_files([("test.dialog.ron".to_string(), &file)]);
+        assert!(duplicates.is_empty());
+        nodes
+    }
+
+    #[test]
+    fn reports_dangling_next() {
+        let nodes = graph(
+            r#"(nodes: [
+                (id: "root", text: "Hi", options: [(label: "Go", next: "nowhere")]),
+            ])"#,
+        );
+        assert_eq!(
+            validate_graph(&nodes, &[]),
+            vec![DialogIssue::DanglingNext {
+                node: "root".to_string(),
+                target: "nowhere".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_unreachable_node_as_warning() {
+        let nodes = graph(
+            r#"(nodes: [
+                (id: "root", text: "Hi", options: [(label: "Bye")]),
+                (id: "lost", text: "Nobody comes here", options: [(label: "Bye")]),
+            ])"#,
+        );
+        let issues = validate_graph(&nodes, &[]);
+        assert_eq!(
+            issues,
+            vec![DialogIssue::Unreachable {
+                node: "lost".to_string(),
+            }]
+        );
+        assert!(!issues[0].is_error());
+    }
+
+    #[test]
+    fn entry_and_npc_targets_are_reachable() {
+        let nodes = graph(
+            r#"(nodes: [
+                (id: "root", text: "Hi", options: [(label: "Bye")]),
+                (id: "demo", entry: true, text: "Demo", options: [(label: "Bye")]),
+                (id: "guard", text: "Halt", options: [(label: "Bye")]),
+            ])"#,
+        );
+        let targets = [("Guard".to_string(), "guard".to_string())];
+        assert!(validate_graph(&nodes, &targets).is_empty());
+    }
+
+    #[test]
+    fn reports_dead_end() {
+        let nodes = graph(
+            r#"(nodes: [
+                (id: "root", text: "Hi", options: [(label: "Go", next: "stuck")]),
+                (id: "stuck", text: "There is no way out"),
+            ])"#,
+        );
+        assert_eq!(
+            validate_graph(&nodes, &[]),
+            vec![DialogIssue::DeadEnd {
+                node: "stuck".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_missing_npc_target() {
+        let nodes = graph(
+            r#"(nodes: [
+                (id: "root", text: "Hi", options: [(label: "Bye")]),
+            ])"#,
+        );
+        let targets = [("Guard".to_string(), "guard".to_string())];
+        assert_eq!(
+            validate_graph(&nodes, &targets),
+            vec![DialogIssue::MissingNpcTarget {
+                npc: "Guard".to_string(),
+                target: "guard".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn asset_dialog_is_valid() {
+        assert_eq!(validate_dialog_cli(None), 0);
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::inventory::Inventory;
+
+/// A dialog variable. Written in dialog files as `true`, `3` or `"text"`.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum DialogValue {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+impl fmt::Display for DialogValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DialogValue::Bool(b) => write!(f, "{}", b),
+            DialogValue::Int(i) => write!(f, "{}", i),
+            DialogValue::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Variables set by dialog effects, plus every node the player has seen.
+#[derive(Resource, Default, Debug)]
+pub struct DialogVars {
+    pub values: HashMap<String, DialogValue>,
+    pub visited: HashSet<String>,
+}
+
+impl DialogVars {
+    pub fn get(&self, name: &str) -> Option<&DialogValue> {
+        self.values.get(name)
+    }
+
+    pub fn set(&mut self, name: &str, value: DialogValue) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    /// Unset variables count as 0.
+    pub fn int(&self, name: &str) -> i64 {
+        match self.values.get(name) {
+            Some(DialogValue::Int(i)) => *i,
+            _ => 0,
+        }
+    }
+
+    /// True if `name` is `true`, a non-zero int or a non-empty string.
+    pub fn flag(&self, name: &str) -> bool {
+        match self.values.get(name) {
+            Some(DialogValue::Bool(b)) => *b,
+            Some(DialogValue::Int(i)) => *i != 0,
+            Some(DialogValue::Str(s)) => !s.is_empty(),
+            None => false,
+        }
+    }
+
+    /// True if every condition holds.
+    pub fn check_all(&self, conditions: &[Condition], inventory: Option<&Inventory>) -> bool {
+        conditions.iter().all(|c| c.check(self, inventory))
+    }
+
+    /// Replaces `{name}` in `text` with the variable's value. Unknown names are left as written.
+    pub fn interpolate(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            match after.find('}') {
+                Some(end) => {
+                    let name = &after[..end];
+                    match self.values.get(name) {
+                        Some(value) => out.push_str(&value.to_string()),
+                        None => out.push_str(&rest[start..start + end + 2]),
+                    }
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    out.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// A check against game state, e.g. `HasItem("key_red")` or `Not(Visited("job"))`.
+#[derive(Clone, Deserialize, Debug)]
+pub enum Condition {
+    /// The player carries at least one of the item.
+    HasItem(String),
+    /// The player carries at least this many of the item.
+    ItemCount(String, u32),
+    /// The variable is set and truthy, see [`DialogVars::flag`].
+    Flag(String),
+    /// The player has seen the node.
+    Visited(String),
+    Equals(String, DialogValue),
+    /// The int variable is at least this value.
+    AtLeast(String, i64),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn check(&self, vars: &DialogVars, inventory: Option<&Inventory>) -> bool {
+        match self {
+            Condition::HasItem(item) => inventory.is_some_and(|inv| inv.has(item)),
+            Condition::ItemCount(item, min) => {
+                inventory.is_some_and(|inv| inv.count(item) >= *min)
+            }
+            Condition::Flag(name) => vars.flag(name),
+            Condition::Visited(node) => vars.visited.contains(node),
+            Condition::Equals(name, value) => vars.get(name) == Some(value),
+            Condition::AtLeast(name, min) => vars.int(name) >= *min,
+            Condition::Not(inner) => !inner.check(vars, inventory),
+        }
+    }
+}
+
+/// A change to [`DialogVars`] made when an option is chosen.
+#[derive(Clone, Deserialize, Debug)]
+pub enum DialogEffect {
+    Set(String, DialogValue),
+    /// Adds to an int variable, starting from 0.
+    Increment(String, i64),
+}
+
+impl DialogEffect {
+    pub fn apply(&self, vars: &mut DialogVars) {
+        match self {
+            DialogEffect::Set(name, value) => vars.set(name, value.clone()),
+            DialogEffect::Increment(name, by) => {
+                let value = vars.int(name) + by;
+                vars.set(name, DialogValue::Int(value));
+            }
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::colliders::Ground;
+use crate::components::Player;
+use crate::dialog::DialogActionsExt;
+use crate::interact::{Interact, InteractAppExt, Verb};
+use crate::inventory::Inventory;
+use crate::trigger::{Trigger, TriggerEntered};
+use crate::Interactable;
+
+const USE_VERB: &str = "use_door";
+
+#[derive(Reflect, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DoorKind {
+    /// Swings around the node's local Y axis. Put the node's origin on the hinge in Blender.
+    #[default]
+    Hinged,
+    /// Moves by `slide` in the node's local space.
+    Sliding,
+}
+
+/// A door or gate. It gets a kinematic box collider and animates between closed and open.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct Door {
+    /// Name used by the `open_gate` dialog action and level scripts.
+    pub id: String,
+    pub kind: DoorKind,
+    /// Degrees, for hinged doors.
+    pub open_angle: f32,
+    /// Local offset, for sliding doors.
+    pub slide: Vec3,
+    /// Seconds to fully open or close.
+    pub duration: f32,
+    pub locked: bool,
+    /// Item that unlocks the door when the player uses it. Empty means the player cannot unlock it.
+    pub key_item: String,
+    /// Whether unlocking the door uses up the key.
+    pub consume_key: bool,
+    /// Id of a `Trigger` that opens the door when entered.
+    pub open_trigger: String,
+}
+
+impl Default for Door {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            kind: DoorKind::Hinged,
+            open_angle: 90.0,
+            slide: Vec3::new(0.0, 2.5, 0.0),
+            duration: 1.0,
+            locked: false,
+            key_item: String::new(),
+            consume_key: false,
+            open_trigger: String::new(),
+        }
+    }
+}
+
+#[derive(Message, Clone, Debug)]
+pub enum DoorRequest {
+    /// `user` interacted with the door: toggles it, unlocking it first if they carry the key.
+    Use { door: Entity, user: Entity },
+    /// Opens every door with this id, unlocking it if it is locked.
+    Open(String),
+}
+
+/// Sent when a door starts opening.
+#[derive(Message, Clone, Debug)]
+pub struct DoorOpened {
+    pub door: Entity,
+    pub id: String,
+}
+
+/// Added to every [`Door`]. Tracks whether it is open and how far it has moved.
+#[derive(Component, Default)]
+pub struct DoorState {
+    /// World position and rotation of the closed door. Taken the first time the door moves, once
+    /// physics has picked up where the level put it.
+    closed: Option<(Vec3, Quat)>,
+    open: bool,
+    /// 0 is closed, 1 is open.
+    progress: f32,
+}
+
+impl DoorState {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Moves the door toward open or closed, without sending [`DoorOpened`]. Used when loading
+    /// a save.
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+}
+
+pub struct DoorPlugin;
+
+impl Plugin for DoorPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Door>()
+            .register_type::<DoorKind>()
+            .add_message::<DoorRequest>()
+            .add_message::<DoorOpened>()
+            .register_dialog_action("open_gate", open_gate_action)
+            .register_interaction::<Door>(Verb::new(USE_VERB, "Open"), use_door)
+            .add_observer(|add: On<Add, Door>, mut commands: Commands| {
+                commands
+                    .entity(add.entity)
+                    .insert((DoorState::default(), RigidBody::Kinematic))
+                    .insert_if_new(Ground::default());
+            })
+            .add_systems(
+                Update,
+                (door_request_system, door_trigger_system, door_verb_system).chain(),
+            )
+            .add_systems(FixedUpdate, door_motion_system);
+    }
+}
+
+fn door_request_system(
+    mut requests: MessageReader<DoorRequest>,
+    mut door_q: Query<(Entity, &mut Door, &mut DoorState)>,
+    mut inventory_q: Query<&mut Inventory>,
+    mut opened: MessageWriter<DoorOpened>,
+) {
+    for request in requests.read() {
+        match request {
+            DoorRequest::Use { door: door_e, user } => {
+                let Ok((_, mut door, mut state)) = door_q.get_mut(*door_e) else {
+                    continue;
+                };
+                if door.locked {
+                    let Ok(mut inventory) = inventory_q.get_mut(*user) else {
+                        continue;
+                    };
+                    if door.key_item.is_empty() || !inventory.has(&door.key_item) {
+                        info!("Door {} is locked", door.id);
+                        continue;
+                    }
+                    if door.consume_key {
+                        inventory.remove(&door.key_item, 1);
+                    }
+                    info!("Door {} unlocked with {}", door.id, door.key_item);
+                    door.locked = false;
+                }
+                state.open = !state.open;
+                if state.open {
+                    opened.write(DoorOpened {
+                        door: *door_e,
+                        id: door.id.clone(),
+                    });
+                }
+            }
+            DoorRequest::Open(id) => {
+                for (e, mut door, mut state) in door_q.iter_mut() {
+                    if door.id != *id {
+                        continue;
+                    }
+                    door.locked = false;
+                    if !state.open {
+                        info!("Opening door {} ({:?})", id, e);
+                        state.open = true;
+                        opened.write(DoorOpened {
+                            door: e,
+                            id: id.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn use_door(In(interact): In<Interact>, mut requests: MessageWriter<DoorRequest>) {
+    requests.write(DoorRequest::Use {
+        door: interact.target,
+        user: interact.user,
+    });
+}
+
+/// `open_gate <door id>`
+fn open_gate_action(In(args): In<Vec<String>>, mut requests: MessageWriter<DoorRequest>) {
+    match args.first() {
+        Some(id) => {
+            requests.write(DoorRequest::Open(id.clone()));
+        }
+        None => warn!("open_gate expects a door id"),
+    }
+}
+
+fn door_trigger_system(
+    mut entered: MessageReader<TriggerEntered>,
+    trigger_q: Query<&Trigger>,
+    mut door_q: Query<(Entity, &mut Door, &mut DoorState)>,
+    mut opened: MessageWriter<DoorOpened>,
+) {
+    for ev in entered.read() {
+        let Ok(trigger) = trigger_q.get(ev.trigger) else {
+            continue;
+        };
+        for (e, mut door, mut state) in door_q.iter_mut() {
+            if door.open_trigger.is_empty() || door.open_trigger != trigger.id {
+                continue;
+            }
+            door.locked = false;
+            if !state.open {
+                state.open = true;
+                opened.write(DoorOpened {
+                    door: e,
+                    id: door.id.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Moves doors toward their open or closed pose by setting their velocity, so that a closing
+/// door pushes the player and props out of the way instead of passing through them.
+fn door_motion_system(
+    time: Res<Time>,
+    mut q: Query<(
+        &Door,
+        &mut DoorState,
+        &Position,
+        &Rotation,
+        &mut LinearVelocity,
+        &mut AngularVelocity,
+    )>,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0.0 {
+        return;
+    }
+    for (door, mut state, pos, rot, mut lin_vel, mut ang_vel) in q.iter_mut() {
+        let target = if state.open { 1.0 } else { 0.0 };
+        if state.progress == target {
+            if lin_vel.0 != Vec3::ZERO || ang_vel.0 != Vec3::ZERO {
+                lin_vel.0 = Vec3::ZERO;
+                ang_vel.0 = Vec3::ZERO;
+            }
+            continue;
+        }
+        let (closed_pos, closed_rot) = *state.closed.get_or_insert((pos.0, rot.0));
+        let step = dt / door.duration.max(0.01);
+        state.progress = if state.open {
+            (state.progress + step).min(1.0)
+        } else {
+            (state.progress - step).max(0.0)
+        };
+        let (target_pos, target_rot) = match door.kind {
+            DoorKind::Hinged => {
+                let swing = Quat::from_rotation_y(door.open_angle.to_radians() * state.progress);
+                (closed_pos, closed_rot * swing)
+            }
+            DoorKind::Sliding => (
+                closed_pos + closed_rot * (door.slide * state.progress),
+                closed_rot,
+            ),
+        };
+        // Velocities that reach the target pose in one physics step.
+        lin_vel.0 = (target_pos - pos.0) / dt;
+        let mut delta = target_rot * rot.0.inverse();
+        if delta.w < 0.0 {
+            delta = -delta;
+        }
+        ang_vel.0 = delta.to_scaled_axis() / dt;
+    }
+}
+
+/// Keeps the prompt in step with the door: "Open" or "Close", "Unlock" when the player carries
+/// the key, and disabled while locked otherwise.
+fn door_verb_system(
+    mut door_q: Query<(&Door, &DoorState, &mut Interactable)>,
+    player_q: Query<&Inventory, With<Player>>,
+) {
+    let inventory = player_q.single().ok();
+    for (door, state, mut interactable) in door_q.iter_mut() {
+        let has_key =
+            !door.key_item.is_empty() && inventory.is_some_and(|inv| inv.has(&door.key_item));
+        let (label, disabled) = match (door.locked, has_key, state.open) {
+            (true, true, _) => ("Unlock", None),
+            (true, false, _) => ("Open", Some("Locked".to_string())),
+            (false, _, true) => ("Close", None),
+            (false, _, false) => ("Open", None),
+        };
+        Interactable::set_verb(&mut interactable, USE_VERB, label, disabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::message::Messages;
+
+    use super::*;
+    use crate::dialog::{run_dialog_actions_system, DialogAction};
+
+    #[test]
+    fn open_gate_requests_the_door() {
+        let mut app = App::new();
+        app.add_message::<DialogAction>()
+            .add_message::<DoorRequest>()
+            .register_dialog_action("open_gate", open_gate_action)
+            .add_systems(Update, run_dialog_actions_system);
+        app.world_mut()
+            .write_message(DialogAction::parse("open_gate east_gate").unwrap());
+        app.update();
+
+        let requests: Vec<_> = app
+            .world()
+            .resource::<Messages<DoorRequest>>()
+            .iter_current_update_messages()
+            .collect();
+        assert!(matches!(requests.as_slice(), [DoorRequest::Open(id)] if id == "east_gate"));
+    }
+
+    fn app_with_door(door: Door) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_message::<DialogAction>()
+            .add_message::<DoorRequest>()
+            .add_message::<DoorOpened>()
+            .register_dialog_action("open_gate", open_gate_action)
+            .add_systems(
+                Update,
+                (run_dialog_actions_system, door_request_system).chain(),
+            );
+        let door = app.world_mut().spawn((door, DoorState::default())).id();
+        (app, door)
+    }
+
+    fn opened(app: &App) -> Vec<String> {
+        app.world()
+            .resource::<Messages<DoorOpened>>()
+            .iter_current_update_messages()
+            .map(|ev| ev.id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn open_gate_opens_and_unlocks_the_door() {
+        let (mut app, door) = app_with_door(Door {
+            id: "east_gate".to_string(),
+            locked: true,
+            ..default()
+        });
+        app.world_mut()
+            .write_message(DialogAction::parse("open_gate east_gate").unwrap());
+        app.update();
+
+        assert!(app.world().get::<DoorState>(door).unwrap().is_open());
+        assert!(!app.world().get::<Door>(door).unwrap().locked);
+        assert_eq!(opened(&app), vec!["east_gate".to_string()]);
+    }
+
+    #[test]
+    fn open_gate_leaves_other_doors_closed() {
+        let (mut app, door) = app_with_door(Door {
+            id: "west_gate".to_string(),
+            ..default()
+        });
+        app.world_mut()
+            .write_message(DialogAction::parse("open_gate east_gate").unwrap());
+        app.update();
+
+        assert!(!app.world().get::<DoorState>(door).unwrap().is_open());
+        assert!(opened(&app).is_empty());
+    }
+
+    #[test]
+    fn locked_door_refuses_a_user_without_the_key() {
+        let (mut app, door) = app_with_door(Door {
+            id: "vault".to_string(),
+            locked: true,
+            key_item: "vault_key".to_string(),
+            ..default()
+        });
+        let user = app.world_mut().spawn(Inventory::default()).id();
+        app.world_mut()
+            .write_message(DoorRequest::Use { door, user });
+        app.update();
+
+        assert!(!app.world().get::<DoorState>(door).unwrap().is_open());
+        assert!(app.world().get::<Door>(door).unwrap().locked);
+        assert!(opened(&app).is_empty());
+    }
+
+    #[test]
+    fn locked_door_opens_for_a_user_with_the_key() {
+        let (mut app, door) = app_with_door(Door {
+            id: "vault".to_string(),
+            locked: true,
+            key_item: "vault_key".to_string(),
+            consume_key: true,
+            ..default()
+        });
+        let mut inventory = Inventory::default();
+        inventory.add("vault_key", 1);
+        let user = app.world_mut().spawn(inventory).id();
+        app.world_mut()
+            .write_message(DoorRequest::Use { door, user });
+        app.update();
+
+        assert!(app.world().get::<DoorState>(door).unwrap().is_open());
+        assert_eq!(
+            app.world()
+                .get::<Inventory>(user)
+                .unwrap()
+                .count("vault_key"),
+            0
+        );
+        assert_eq!(opened(&app), vec!["vault".to_string()]);
+    }
+}
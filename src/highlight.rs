@@ -0,0 +1,77 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::interact::InteractionTarget;
+
+/// Added to the emissive colour of the targeted interactable's materials.
+const HIGHLIGHT_EMISSIVE: LinearRgba = LinearRgba::rgb(0.3, 0.25, 0.08);
+
+/// A mesh of the targeted interactable, showing a highlight material in place of `original`.
+#[derive(Component)]
+struct Highlighted {
+    original: Handle<StandardMaterial>,
+}
+
+/// Highlight version of each material, made the first time something using it is targeted.
+#[derive(Resource, Default)]
+struct HighlightMaterials(HashMap<AssetId<StandardMaterial>, Handle<StandardMaterial>>);
+
+pub struct HighlightPlugin;
+
+impl Plugin for HighlightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HighlightMaterials>()
+            .add_systems(Update, highlight_target_system);
+    }
+}
+
+/// Swaps the materials of the target and its descendant meshes for tinted copies, and puts the
+/// originals back when the target changes.
+#[allow(clippy::too_many_arguments)]
+fn highlight_target_system(
+    mut commands: Commands,
+    target: Res<InteractionTarget>,
+    mut last: Local<Option<Entity>>,
+    children_q: Query<&Children>,
+    mut material_q: Query<&mut MeshMaterial3d<StandardMaterial>>,
+    highlighted_q: Query<(Entity, &Highlighted)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut cache: ResMut<HighlightMaterials>,
+) {
+    if *last == target.entity {
+        return;
+    }
+    *last = target.entity;
+
+    for (e, highlighted) in highlighted_q.iter() {
+        if let Ok(mut material) = material_q.get_mut(e) {
+            material.0 = highlighted.original.clone();
+        }
+        commands.entity(e).remove::<Highlighted>();
+    }
+
+    let Some(root) = target.entity else {
+        return;
+    };
+    for e in std::iter::once(root).chain(children_q.iter_descendants(root)) {
+        let Ok(mut material) = material_q.get_mut(e) else {
+            continue;
+        };
+        let original = material.0.clone();
+        let highlight = match cache.0.get(&original.id()) {
+            Some(highlight) => highlight.clone(),
+            None => {
+                // Not loaded yet; it will be highlighted next time it is targeted.
+                let Some(source) = materials.get(&original) else {
+                    continue;
+                };
+                let mut tinted = source.clone();
+                tinted.emissive += HIGHLIGHT_EMISSIVE;
+                let highlight = materials.add(tinted);
+                cache.0.insert(original.id(), highlight.clone());
+                highlight
+            }
+        };
+        material.0 = highlight;
+        commands.entity(e).insert(Highlighted { original });
+    }
+}
@@ -1,79 +1,382 @@
-use bevy::prelude::*;
+use avian3d::prelude::*;
+use bevy::{
+    ecs::{component::ComponentId, system::SystemId},
+    prelude::*,
+};
 use bevy_egui::{egui, EguiContexts, EguiPrimaryContextPass};
 
-use crate::dialog::{DialogState, Npc};
+use crate::carry::{self, Held};
+use crate::components::Player;
+use crate::dialog::{DialogInputSet, DialogState};
+use crate::layers::GameLayer;
 use crate::Interactable;
 
-#[derive(Component, Reflect)]
-#[reflect(Component)]
-pub struct Door;
+/// Length of the interaction ray. Nothing further away is targeted, whatever its range.
+const MAX_RANGE: f32 = 10.0;
+const HOLD_RING_RADIUS: f32 = 12.0;
 
-fn crosshair_and_interact_system(
-    mut contexts: EguiContexts,
-    camera_q: Query<(&GlobalTransform, &Camera), With<Camera>>,
-    interactable_q: Query<(Entity, &GlobalTransform), With<Interactable>>,
-    npc_q: Query<&Npc>,
-    door_q: Query<&Door>,
+/// One thing that can be done to an [`Interactable`], e.g. "Talk" or "Pick up".
+#[derive(Reflect, Clone, PartialEq, Default, Debug)]
+pub struct Verb {
+    /// Identifies the verb to its handler.
+    pub name: String,
+    /// Shown in the prompt.
+    pub label: String,
+    /// Seconds the interact key has to be held. 0 means a press.
+    pub hold: f32,
+    /// Why the verb can't be used right now, e.g. "Locked". It is still shown, greyed out.
+    pub disabled: Option<String>,
+}
+
+impl Verb {
+    pub fn new(name: &str, label: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            label: label.to_string(),
+            ..default()
+        }
+    }
+
+    pub fn with_hold(mut self, secs: f32) -> Self {
+        self.hold = secs;
+        self
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.disabled.is_none()
+    }
+}
+
+impl Interactable {
+    /// Sets the label and disabled reason of the verb named `name`, if there is one. `this` is
+    /// only written to when something changes, so the component isn't marked changed every frame.
+    pub fn set_verb(this: &mut Mut<Self>, name: &str, label: &str, disabled: Option<String>) {
+        let Some(index) = this.verbs.iter().position(|v| v.name == name) else {
+            return;
+        };
+        let verb = &this.verbs[index];
+        if verb.label != label || verb.disabled != disabled {
+            let verb = &mut this.verbs[index];
+            verb.label = label.to_string();
+            verb.disabled = disabled;
+        }
+    }
+}
+
+/// `user` used `verb` on `target`.
+#[derive(Message, Clone, Debug)]
+pub struct Interact {
+    pub target: Entity,
+    pub user: Entity,
+    pub verb: String,
+}
+
+/// Key bindings for interaction.
+#[derive(Resource)]
+pub struct InteractKeys {
+    pub interact: KeyCode,
+    /// Selects the next press verb when the target has more than one.
+    pub next_verb: KeyCode,
+}
+
+impl Default for InteractKeys {
+    fn default() -> Self {
+        Self {
+            interact: KeyCode::KeyE,
+            next_verb: KeyCode::KeyR,
+        }
+    }
+}
+
+/// A short name for a key, as shown in prompts: `KeyE` is "E", `Digit1` is "1".
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    ["Key", "Digit"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .map_or(name.clone(), str::to_string)
+}
+
+/// The `Interactable` the player is looking at.
+///
+/// Targeting policy: a ray from the camera against [`GameLayer::interact_mask`]. If the nearest
+/// hit belongs to an interactable (the collider's entity or one of its ancestors) within that
+/// interactable's `range`, it is the target; world geometry in front of it blocks it. Nothing is
+/// targeted while dialog is open or a prop is being carried.
+#[derive(Resource, Default)]
+pub struct InteractionTarget {
+    pub entity: Option<Entity>,
+    /// Seconds the interact key has been held on `entity`.
+    pub held_for: f32,
+    /// Which of the target's enabled press verbs the interact key uses.
+    pub selected: usize,
+}
+
+struct Provider {
+    component: ComponentId,
+    verb: String,
+    handler: SystemId<In<Interact>>,
+}
+
+/// Components that can be interacted with, and what happens when they are.
+#[derive(Resource, Default)]
+pub struct InteractionProviders(Vec<Provider>);
+
+pub trait InteractAppExt {
+    /// Makes every entity with `T` [`Interactable`] with `verb`. When the verb is used,
+    /// `handler` runs with the [`Interact`] message.
+    fn register_interaction<T: Component, M>(
+        &mut self,
+        verb: Verb,
+        handler: impl IntoSystem<In<Interact>, (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl InteractAppExt for App {
+    fn register_interaction<T: Component, M>(
+        &mut self,
+        verb: Verb,
+        handler: impl IntoSystem<In<Interact>, (), M> + 'static,
+    ) -> &mut Self {
+        let handler = self.register_system(handler);
+        let component = self.world_mut().register_component::<T>();
+        self.world_mut()
+            .get_resource_or_init::<InteractionProviders>()
+            .0
+            .push(Provider {
+                component,
+                verb: verb.name.clone(),
+                handler,
+            });
+        self.add_observer(move |add: On<Add, T>, mut commands: Commands| {
+            let verb = verb.clone();
+            commands
+                .entity(add.entity)
+                .entry::<Interactable>()
+                .or_default()
+                .and_modify(move |mut interactable| {
+                    if !interactable.verbs.iter().any(|v| v.name == verb.name) {
+                        interactable.verbs.push(verb);
+                    }
+                });
+        })
+    }
+}
+
+fn interaction_target_system(
+    spatial: SpatialQuery,
+    camera_q: Query<&GlobalTransform, With<Camera>>,
+    interactable_q: Query<&Interactable>,
+    parent_q: Query<&ChildOf>,
+    held_q: Query<(), With<Held>>,
+    dialog_state: Res<DialogState>,
+    mut target: ResMut<InteractionTarget>,
+) {
+    let previous = target.entity;
+    target.entity = None;
+    if dialog_state.open || !held_q.is_empty() {
+        return;
+    }
+    let Ok(cam_transform) = camera_q.single() else {
+        return;
+    };
+    let filter = SpatialQueryFilter::from_mask(GameLayer::interact_mask());
+    let Some(hit) = spatial.cast_ray(
+        cam_transform.translation(),
+        cam_transform.forward(),
+        MAX_RANGE,
+        true,
+        &filter,
+    ) else {
+        return;
+    };
+    target.entity = std::iter::once(hit.entity)
+        .chain(parent_q.iter_ancestors(hit.entity))
+        .find_map(|e| interactable_q.get(e).ok().map(|i| (e, i.range)))
+        .filter(|(_, range)| hit.distance <= *range)
+        .map(|(e, _)| e);
+    if target.entity != previous {
+        target.held_for = 0.0;
+        target.selected = 0;
+    }
+}
+
+/// The enabled verbs that are a press.
+fn presses(verbs: &[Verb]) -> impl Iterator<Item = &Verb> {
+    verbs.iter().filter(|v| v.enabled() && v.hold <= 0.0)
+}
+
+/// The selected enabled press verb, and the first enabled verb that is a hold.
+fn press_and_hold(verbs: &[Verb], selected: usize) -> (Option<&Verb>, Option<&Verb>) {
+    let count = presses(verbs).count().max(1);
+    let press = presses(verbs).nth(selected % count);
+    let hold = verbs.iter().find(|v| v.enabled() && v.hold > 0.0);
+    (press, hold)
+}
+
+/// A press verb fires when the key goes down, or when it comes back up if the target also has a
+/// hold verb. A hold verb fires once the key has been held long enough. When the target has
+/// several press verbs, the next-verb key picks which one the interact key uses.
+#[allow(clippy::too_many_arguments)]
+fn interact_input_system(
+    mut commands: Commands,
+    time: Res<Time>,
     keys: Res<ButtonInput<KeyCode>>,
-    mouse: Res<ButtonInput<MouseButton>>,
-    mut dialog_state: ResMut<DialogState>,
+    bindings: Res<InteractKeys>,
+    mut target: ResMut<InteractionTarget>,
+    interactable_q: Query<&Interactable>,
+    player_q: Query<Entity, With<Player>>,
+    held_q: Query<Entity, With<Held>>,
+    mut interactions: MessageWriter<Interact>,
 ) {
-    let ctx = match contexts.ctx_mut() {
-        Ok(c) => c,
-        Err(_) => return,
+    let key = bindings.interact;
+    // While carrying, the key puts the prop down instead.
+    if keys.just_pressed(key) {
+        if let Some(held) = held_q.iter().next() {
+            carry::release(&mut commands, held);
+            return;
+        }
+    }
+    let (Some(entity), Ok(user)) = (target.entity, player_q.single()) else {
+        return;
+    };
+    let Ok(interactable) = interactable_q.get(entity) else {
+        return;
     };
-    let mut hit = None;
-    if let Ok((cam_transform, _cam)) = camera_q.single() {
-        let origin = cam_transform.translation();
-        let dir = cam_transform.forward();
-        let max_dist = 5.0;
-        for (entity, t) in interactable_q.iter() {
-            let to = t.translation() - origin;
-            let proj = to.dot(*dir);
-            if proj > 0.0 && proj < max_dist {
-                let closest = origin + (*dir) * proj;
-                if (closest - t.translation()).length() < 0.5 {
-                    hit = Some(entity);
-                    break;
+    if keys.just_pressed(bindings.next_verb) {
+        let count = presses(&interactable.verbs).count().max(1);
+        target.selected = (target.selected + 1) % count;
+    }
+    let (press, hold) = press_and_hold(&interactable.verbs, target.selected);
+
+    let mut used = None;
+    if keys.pressed(key) {
+        let was_held = target.held_for;
+        target.held_for += time.delta_secs();
+        match (press, hold) {
+            (Some(press), None) if keys.just_pressed(key) => used = Some(press),
+            (_, Some(hold)) if was_held < hold.hold && target.held_for >= hold.hold => {
+                used = Some(hold)
+            }
+            _ => {}
+        }
+    } else {
+        if keys.just_released(key) {
+            if let (Some(press), Some(hold)) = (press, hold) {
+                if target.held_for < hold.hold {
+                    used = Some(press);
                 }
             }
         }
+        target.held_for = 0.0;
+    }
+
+    if let Some(verb) = used {
+        info!("{} on entity {:?}", verb.label, entity);
+        interactions.write(Interact {
+            target: entity,
+            user,
+            verb: verb.name.clone(),
+        });
     }
+}
+
+/// Runs the handler registered for the verb on each of the target's components.
+fn dispatch_interactions_system(
+    mut commands: Commands,
+    mut interactions: MessageReader<Interact>,
+    providers: Res<InteractionProviders>,
+    entity_q: Query<EntityRef>,
+) {
+    for interact in interactions.read() {
+        let Ok(entity) = entity_q.get(interact.target) else {
+            continue;
+        };
+        for provider in providers.0.iter() {
+            if provider.verb == interact.verb && entity.contains_id(provider.component) {
+                commands.run_system_with(provider.handler, interact.clone());
+            }
+        }
+    }
+}
+
+fn crosshair_system(
+    mut contexts: EguiContexts,
+    target: Res<InteractionTarget>,
+    bindings: Res<InteractKeys>,
+    interactable_q: Query<&Interactable>,
+) {
+    let ctx = match contexts.ctx_mut() {
+        Ok(c) => c,
+        Err(_) => return,
+    };
     let screen_center = ctx.screen_rect().center();
-    let radius = if hit.is_some() { 4.0 } else { 2.0 };
+    let radius = if target.entity.is_some() { 4.0 } else { 2.0 };
     let painter = ctx.layer_painter(egui::LayerId::new(
         egui::Order::Foreground,
         egui::Id::new("crosshair"),
     ));
     painter.circle_filled(screen_center, radius, egui::Color32::WHITE);
-    if hit.is_some() {
-        let hint_offset = egui::vec2(0.0, 20.0);
-        painter.text(
-            screen_center + hint_offset,
-            egui::Align2::CENTER_TOP,
-            "Press E to interact",
-            egui::FontId::proportional(16.0),
-            egui::Color32::YELLOW,
-        );
+    let Some(interactable) = target.entity.and_then(|e| interactable_q.get(e).ok()) else {
+        return;
+    };
+
+    let key = key_name(bindings.interact);
+    let (press, hold) = press_and_hold(&interactable.verbs, target.selected);
+    if let Some(hold) = hold.filter(|_| target.held_for > 0.0) {
+        let progress = (target.held_for / hold.hold).min(1.0);
+        let points: Vec<egui::Pos2> = (0..=32)
+            .map(|i| {
+                let angle = std::f32::consts::TAU * progress * i as f32 / 32.0
+                    - std::f32::consts::FRAC_PI_2;
+                screen_center + egui::vec2(angle.cos(), angle.sin()) * HOLD_RING_RADIUS
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(3.0, egui::Color32::YELLOW),
+        ));
     }
-    if let Some(target) = hit {
-        if keys.just_pressed(KeyCode::KeyE) {
-            if let Ok(npc) = npc_q.get(target) {
-                if !npc.dialogue_id.is_empty() {
-                    dialog_state.open = true;
-                    dialog_state.current = Some(Box::leak(npc.dialogue_id.clone().into_boxed_str()));
-                    dialog_state.visible_chars = 0;
-                }
-            }
 
-            if mouse.just_pressed(MouseButton::Right) {
-                if let Ok(_door) = door_q.get(target) {
-                    info!("Door interacted: {:?}", target);
-                }
+    let mut lines = Vec::new();
+    for verb in interactable.verbs.iter() {
+        let binding = if verb.hold > 0.0 {
+            format!("Hold {}", key)
+        } else {
+            key.clone()
+        };
+        lines.push(match &verb.disabled {
+            Some(reason) => (
+                format!("[{}] {} ({})", binding, verb.label, reason),
+                egui::Color32::GRAY,
+            ),
+            // A press verb the interact key is not on right now.
+            None if verb.hold <= 0.0 && press != Some(verb) => {
+                (verb.label.clone(), egui::Color32::WHITE)
             }
+            None => (
+                format!("[{}] {}", binding, verb.label),
+                egui::Color32::YELLOW,
+            ),
+        });
+    }
+    if presses(&interactable.verbs).count() > 1 {
+        lines.push((
+            format!("[{}] Next", key_name(bindings.next_verb)),
+            egui::Color32::WHITE,
+        ));
+    }
 
-            info!("Interacted with entity: {:?}", target);
-        }
+    let mut pos = screen_center + egui::vec2(0.0, 20.0);
+    for (text, color) in lines {
+        let rect = painter.text(
+            pos,
+            egui::Align2::CENTER_TOP,
+            text,
+            egui::FontId::proportional(16.0),
+            color,
+        );
+        pos.y = rect.bottom() + 2.0;
     }
 }
 
@@ -81,7 +384,21 @@ pub struct InteractPlugin;
 
 impl Plugin for InteractPlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<Door>()
-            .add_systems(EguiPrimaryContextPass, crosshair_and_interact_system);
+        app.register_type::<Verb>()
+            .init_resource::<InteractionTarget>()
+            .init_resource::<InteractionProviders>()
+            .init_resource::<InteractKeys>()
+            .add_message::<Interact>()
+            .add_systems(
+                Update,
+                (
+                    interaction_target_system,
+                    interact_input_system,
+                    dispatch_interactions_system,
+                )
+                    .chain()
+                    .after(DialogInputSet),
+            )
+            .add_systems(EguiPrimaryContextPass, crosshair_system);
     }
 }
@@ -0,0 +1,69 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::Deserialize;
+
+use crate::ron_asset::RonAsset;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ItemDef {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Image path under `assets/`.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// The most of this item the player can carry.
+    #[serde(default = "default_stack_size")]
+    pub stack_size: u32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn default_stack_size() -> u32 {
+    99
+}
+
+/// Every item definition, by id.
+#[derive(Resource, Default)]
+pub struct ItemDefs(pub HashMap<String, ItemDef>);
+
+impl ItemDefs {
+    pub fn get(&self, id: &str) -> Option<&ItemDef> {
+        self.0.get(id)
+    }
+
+    /// Unknown items have no limit.
+    pub fn stack_size(&self, id: &str) -> u32 {
+        self.get(id).map_or(u32::MAX, |def| def.stack_size)
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct ItemFile {
+    pub items: Vec<ItemDef>,
+}
+
+impl RonAsset for ItemFile {
+    const EXTENSION: &'static str = "items.ron";
+    const FOLDER: &'static str = "items";
+    type Merged = ItemDefs;
+
+    /// The first file to define an item wins.
+    fn merge(files: &[(String, &Self)], defs: &mut ItemDefs) {
+        let mut items = HashMap::new();
+        for (path, file) in files.iter() {
+            for def in file.items.iter() {
+                if items.contains_key(&def.id) {
+                    warn!(
+                        "{}: item {:?} is already defined in another file",
+                        path, def.id
+                    );
+                    continue;
+                }
+                items.insert(def.id.clone(), def.clone());
+            }
+        }
+        info!("Item definitions rebuilt: {} items", items.len());
+        defs.0 = items;
+    }
+}
@@ -0,0 +1,214 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_egui::EguiPrimaryContextPass;
+
+use crate::components::Player;
+use crate::dialog::DialogActionsExt;
+use crate::ron_asset::RonAssetAppExt;
+
+pub mod items;
+pub mod pickup;
+mod ui;
+
+pub use items::{ItemDef, ItemDefs};
+
+/// Items carried by an entity, by item id.
+#[derive(Component, Default, Debug)]
+pub struct Inventory {
+    items: HashMap<String, u32>,
+}
+
+impl Inventory {
+    pub fn add(&mut self, item: &str, count: u32) {
+        *self.items.entry(item.to_string()).or_default() += count;
+    }
+
+    /// Adds up to `count` of `item` without going over its stack size. Returns how many were added.
+    pub fn add_stacked(&mut self, defs: &ItemDefs, item: &str, count: u32) -> u32 {
+        let room = defs.stack_size(item).saturating_sub(self.count(item));
+        let added = count.min(room);
+        if added > 0 {
+            self.add(item, added);
+        }
+        added
+    }
+
+    /// Removes `count` of `item`. Returns false, leaving the inventory untouched, if there are not enough.
+    pub fn remove(&mut self, item: &str, count: u32) -> bool {
+        match self.items.get_mut(item) {
+            Some(have) if *have >= count => {
+                *have -= count;
+                if *have == 0 {
+                    self.items.remove(item);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn count(&self, item: &str) -> u32 {
+        self.items.get(item).copied().unwrap_or(0)
+    }
+
+    pub fn has(&self, item: &str) -> bool {
+        self.count(item) > 0
+    }
+
+    /// Every item carried, with its count, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.items
+            .iter()
+            .map(|(item, count)| (item.as_str(), *count))
+    }
+}
+
+/// The count of `item` in `entity`'s inventory went from `old` to `new`.
+#[derive(Message, Clone, Debug)]
+pub struct InventoryChanged {
+    pub entity: Entity,
+    pub item: String,
+    pub old: u32,
+    pub new: u32,
+}
+
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_ron_asset::<items::ItemFile>()
+            .init_resource::<ItemDefs>()
+            .init_resource::<ui::InventoryScreen>()
+            .add_message::<InventoryChanged>()
+            .register_dialog_action("give_item", give_item_action)
+            .register_dialog_action("take_item", take_item_action)
+            .add_systems(
+                Update,
+                (inventory_changed_system, ui::toggle_inventory_system),
+            )
+            .add_systems(EguiPrimaryContextPass, ui::inventory_egui_system);
+    }
+}
+
+/// Compares each changed inventory with how it looked last time and sends an
+/// [`InventoryChanged`] for every item whose count differs.
+fn inventory_changed_system(
+    inventory_q: Query<(Entity, &Inventory), Changed<Inventory>>,
+    mut removed: RemovedComponents<Inventory>,
+    mut last_seen: Local<HashMap<Entity, HashMap<String, u32>>>,
+    mut changes: MessageWriter<InventoryChanged>,
+) {
+    for entity in removed.read() {
+        last_seen.remove(&entity);
+    }
+    for (entity, inventory) in inventory_q.iter() {
+        let old = last_seen.entry(entity).or_default();
+        for (item, &new) in inventory.items.iter() {
+            let was = old.get(item).copied().unwrap_or(0);
+            if was != new {
+                changes.write(InventoryChanged {
+                    entity,
+                    item: item.clone(),
+                    old: was,
+                    new,
+                });
+            }
+        }
+        for (item, &was) in old.iter() {
+            if !inventory.items.contains_key(item) {
+                changes.write(InventoryChanged {
+                    entity,
+                    item: item.clone(),
+                    old: was,
+                    new: 0,
+                });
+            }
+        }
+        *old = inventory.items.clone();
+    }
+}
+
+/// `give_item <item> [count]`: adds to the player's inventory, up to the item's stack size.
+fn give_item_action(
+    In(args): In<Vec<String>>,
+    defs: Res<ItemDefs>,
+    mut player_q: Query<&mut Inventory, With<Player>>,
+) {
+    let Some(item) = args.first() else {
+        warn!("give_item expects an item id");
+        return;
+    };
+    if defs.get(item).is_none() {
+        warn!("give_item: unknown item {:?}", item);
+    }
+    let count = args.get(1).and_then(|c| c.parse().ok()).unwrap_or(1);
+    if let Ok(mut inventory) = player_q.single_mut() {
+        let added = inventory.add_stacked(&defs, item, count);
+        info!("Received item: {} x{}", item, added);
+    }
+}
+
+/// `take_item <item> [count]`: removes from the player's inventory, if they carry enough.
+fn take_item_action(In(args): In<Vec<String>>, mut player_q: Query<&mut Inventory, With<Player>>) {
+    let Some(item) = args.first() else {
+        warn!("take_item expects an item id");
+        return;
+    };
+    let count = args.get(1).and_then(|c| c.parse().ok()).unwrap_or(1);
+    if let Ok(mut inventory) = player_q.single_mut() {
+        if inventory.remove(item, count) {
+            info!("Gave away item: {} x{}", item, count);
+        } else {
+            warn!("take_item: player does not have {} x{}", item, count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialog::{run_dialog_actions_system, DialogAction};
+
+    fn app_with_player() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_message::<DialogAction>()
+            .init_resource::<ItemDefs>()
+            .register_dialog_action("give_item", give_item_action)
+            .add_systems(Update, run_dialog_actions_system);
+        let player = app.world_mut().spawn((Player, Inventory::default())).id();
+        (app, player)
+    }
+
+    fn send(app: &mut App, action: &str) {
+        app.world_mut()
+            .write_message(DialogAction::parse(action).unwrap());
+        app.update();
+    }
+
+    #[test]
+    fn give_item_adds_to_the_player() {
+        let (mut app, player) = app_with_player();
+        send(&mut app, "give_item coin 3");
+        send(&mut app, "give_item coin");
+        let inventory = app.world().get::<Inventory>(player).unwrap();
+        assert_eq!(inventory.count("coin"), 4);
+    }
+
+    #[test]
+    fn give_item_stops_at_the_stack_size() {
+        let (mut app, player) = app_with_player();
+        app.world_mut().resource_mut::<ItemDefs>().0.insert(
+            "key".to_string(),
+            ItemDef {
+                id: "key".to_string(),
+                name: "Key".to_string(),
+                description: String::new(),
+                icon: None,
+                stack_size: 1,
+                tags: Vec::new(),
+            },
+        );
+        send(&mut app, "give_item key 2");
+        let inventory = app.world().get::<Inventory>(player).unwrap();
+        assert_eq!(inventory.count("key"), 1);
+    }
+}
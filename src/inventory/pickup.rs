@@ -0,0 +1,202 @@
+use bevy::{ecs::system::SystemParam, platform::collections::HashSet, prelude::*};
+
+use super::{Inventory, ItemDefs};
+use crate::components::Player;
+use crate::interact::{Interact, InteractAppExt, Verb};
+use crate::Interactable;
+
+const TAKE_VERB: &str = "take";
+/// Horizontal distance from the player's centre at which walking over a pickup collects it.
+const WALK_OVER_RADIUS: f32 = 1.0;
+/// Vertical distance from the player's centre (half their height, plus a little).
+const WALK_OVER_HEIGHT: f32 = 2.0;
+
+/// An item lying in the level. The player collects it by walking over it or by taking it.
+///
+/// Collected pickups are remembered by the node's `Name`, so they stay gone when the level is
+/// loaded again. Give every pickup node in Blender a unique name.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct Pickup {
+    pub item_id: String,
+    pub count: u32,
+    /// Collect it by walking over it. Otherwise it has to be taken with the interact key.
+    pub walk_over: bool,
+}
+
+impl Default for Pickup {
+    fn default() -> Self {
+        Self {
+            item_id: String::new(),
+            count: 1,
+            walk_over: true,
+        }
+    }
+}
+
+/// `entity` collected `count` of `item` from a pickup at `position`.
+#[derive(Message, Clone, Debug)]
+pub struct PickedUp {
+    pub entity: Entity,
+    pub item: String,
+    pub count: u32,
+    pub position: Vec3,
+}
+
+/// Names of the pickups that have been collected.
+#[derive(Resource, Default, Debug)]
+pub struct CollectedPickups(pub HashSet<String>);
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Pickup>()
+            .init_resource::<CollectedPickups>()
+            .add_message::<PickedUp>()
+            .register_interaction::<Pickup>(Verb::new(TAKE_VERB, "Take"), take_pickup)
+            .add_systems(
+                Update,
+                (
+                    remove_collected_pickups_system,
+                    walk_over_pickup_system,
+                    pickup_verb_system,
+                ),
+            );
+    }
+}
+
+/// What collecting a pickup touches besides the pickup and the inventory.
+#[derive(SystemParam)]
+struct Collector<'w, 's> {
+    commands: Commands<'w, 's>,
+    collected: ResMut<'w, CollectedPickups>,
+    defs: Res<'w, ItemDefs>,
+    messages: MessageWriter<'w, PickedUp>,
+}
+
+impl Collector<'_, '_> {
+    /// Adds the pickup to `user`'s inventory, as much as fits, and despawns it once it is empty.
+    fn collect(
+        &mut self,
+        pickup_e: Entity,
+        pickup: &mut Pickup,
+        name: Option<&Name>,
+        position: Vec3,
+        user: Entity,
+        inventory: &mut Inventory,
+    ) {
+        let added = inventory.add_stacked(&self.defs, &pickup.item_id, pickup.count);
+        if added == 0 {
+            return;
+        }
+        info!("Picked up {} x{}", pickup.item_id, added);
+        self.messages.write(PickedUp {
+            entity: user,
+            item: pickup.item_id.clone(),
+            count: added,
+            position,
+        });
+        pickup.count -= added;
+        if pickup.count > 0 {
+            return;
+        }
+        match name {
+            Some(name) => {
+                self.collected.0.insert(name.to_string());
+            }
+            None => warn!(
+                "Pickup {:?} has no name, so it will be back next time the level loads",
+                pickup_e
+            ),
+        }
+        self.commands.entity(pickup_e).despawn();
+    }
+}
+
+fn take_pickup(
+    In(interact): In<Interact>,
+    mut collector: Collector,
+    mut pickup_q: Query<(&mut Pickup, Option<&Name>, &GlobalTransform)>,
+    mut inventory_q: Query<&mut Inventory>,
+) {
+    let Ok((mut pickup, name, transform)) = pickup_q.get_mut(interact.target) else {
+        return;
+    };
+    let Ok(mut inventory) = inventory_q.get_mut(interact.user) else {
+        return;
+    };
+    collector.collect(
+        interact.target,
+        &mut pickup,
+        name,
+        transform.translation(),
+        interact.user,
+        &mut inventory,
+    );
+}
+
+fn walk_over_pickup_system(
+    mut collector: Collector,
+    mut pickup_q: Query<(Entity, &mut Pickup, Option<&Name>, &GlobalTransform)>,
+    mut player_q: Query<(Entity, &GlobalTransform, &mut Inventory), With<Player>>,
+) {
+    let Ok((player_e, player_transform, mut inventory)) = player_q.single_mut() else {
+        return;
+    };
+    let player_pos = player_transform.translation();
+    for (e, mut pickup, name, transform) in pickup_q.iter_mut() {
+        if !pickup.walk_over {
+            continue;
+        }
+        let offset = transform.translation() - player_pos;
+        if offset.xz().length() > WALK_OVER_RADIUS || offset.y.abs() > WALK_OVER_HEIGHT {
+            continue;
+        }
+        collector.collect(
+            e,
+            &mut pickup,
+            name,
+            transform.translation(),
+            player_e,
+            &mut inventory,
+        );
+    }
+}
+
+/// Despawns pickups that were collected before the level was last loaded.
+fn remove_collected_pickups_system(
+    mut commands: Commands,
+    collected: Res<CollectedPickups>,
+    pickup_q: Query<(Entity, &Name), Added<Pickup>>,
+) {
+    for (e, name) in pickup_q.iter() {
+        if collected.0.contains(name.as_str()) {
+            commands.entity(e).despawn();
+        }
+    }
+}
+
+/// Names the item in the prompt, e.g. "Take Old Coin (3)", and disables it when the player has
+/// no room for any more.
+fn pickup_verb_system(
+    defs: Res<ItemDefs>,
+    player_q: Query<&Inventory, With<Player>>,
+    mut pickup_q: Query<(&Pickup, &mut Interactable)>,
+) {
+    let inventory = player_q.single().ok();
+    for (pickup, mut interactable) in pickup_q.iter_mut() {
+        let name = defs
+            .get(&pickup.item_id)
+            .map_or(pickup.item_id.as_str(), |def| def.name.as_str());
+        let label = if pickup.count > 1 {
+            format!("Take {} ({})", name, pickup.count)
+        } else {
+            format!("Take {}", name)
+        };
+        let full = inventory
+            .is_some_and(|inv| inv.count(&pickup.item_id) >= defs.stack_size(&pickup.item_id));
+        let disabled = full.then(|| "Full".to_string());
+        Interactable::set_verb(&mut interactable, TAKE_VERB, &label, disabled);
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_egui::{egui, EguiContexts, EguiTextureHandle};
+
+use super::{Inventory, ItemDefs};
+use crate::components::Player;
+use crate::dialog::DialogState;
+
+const TOGGLE_KEY: KeyCode = KeyCode::Tab;
+const ICON_SIZE: f32 = 32.0;
+
+#[derive(Resource, Default)]
+pub struct InventoryScreen {
+    pub open: bool,
+    /// Egui textures for item icons, by icon path.
+    icons: HashMap<String, egui::TextureId>,
+}
+
+pub fn toggle_inventory_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    dialog_state: Res<DialogState>,
+    mut screen: ResMut<InventoryScreen>,
+) {
+    if keys.just_pressed(TOGGLE_KEY) && !dialog_state.open {
+        screen.open = !screen.open;
+    }
+}
+
+/// Lists the player's items, sorted by name, with their icons and counts. Hovering an item shows
+/// its description and tags.
+pub fn inventory_egui_system(
+    mut contexts: EguiContexts,
+    mut screen: ResMut<InventoryScreen>,
+    defs: Res<ItemDefs>,
+    asset_server: Res<AssetServer>,
+    player_q: Query<&Inventory, With<Player>>,
+) {
+    if !screen.open {
+        return;
+    }
+    let Ok(inventory) = player_q.single() else {
+        return;
+    };
+
+    let mut items: Vec<_> = inventory
+        .iter()
+        .map(|(id, count)| (id, count, defs.get(id)))
+        .collect();
+    items.sort_by_key(|(id, _, def)| def.map_or(*id, |def| def.name.as_str()));
+
+    let mut icons = Vec::with_capacity(items.len());
+    for (_, _, def) in items.iter() {
+        let icon = def.and_then(|def| def.icon.as_ref()).map(|path| {
+            *screen.icons.entry(path.clone()).or_insert_with(|| {
+                contexts.add_image(EguiTextureHandle::Strong(asset_server.load(path)))
+            })
+        });
+        icons.push(icon);
+    }
+
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+    let mut open = screen.open;
+    egui::Window::new("Inventory")
+        .open(&mut open)
+        .default_size([320.0, 400.0])
+        .show(ctx, |ui| {
+            if items.is_empty() {
+                ui.label("You are not carrying anything.");
+                return;
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for ((id, count, def), icon) in items.iter().zip(icons) {
+                    let row = ui.horizontal(|ui| {
+                        match icon {
+                            Some(texture) => {
+                                ui.image(egui::load::SizedTexture::new(
+                                    texture,
+                                    [ICON_SIZE, ICON_SIZE],
+                                ));
+                            }
+                            None => {
+                                ui.add_space(ICON_SIZE + ui.spacing().item_spacing.x);
+                            }
+                        }
+                        let name = def.map_or(*id, |def| def.name.as_str());
+                        ui.label(egui::RichText::new(name).strong());
+                        if *count > 1 {
+                            ui.label(format!("x{}", count));
+                        }
+                    });
+                    if let Some(def) = def {
+                        row.response.on_hover_ui(|ui| {
+                            ui.label(egui::RichText::new(def.name.as_str()).strong());
+                            if !def.description.is_empty() {
+                                ui.label(def.description.as_str());
+                            }
+                            if !def.tags.is_empty() {
+                                ui.label(
+                                    egui::RichText::new(def.tags.join(", "))
+                                        .small()
+                                        .color(egui::Color32::GRAY),
+                                );
+                            }
+                        });
+                    }
+                }
+            });
+        });
+    screen.open = open;
+}
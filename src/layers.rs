@@ -0,0 +1,105 @@
+use avian3d::prelude::*;
+
+/// Collision layers used by every collider in the game.
+#[derive(PhysicsLayer, Default, Clone, Copy, Debug)]
+pub enum GameLayer {
+    #[default]
+    Default,
+    Player,
+    /// Static level geometry (`Ground`).
+    World,
+    /// Dynamic props (`PhysicsObject`).
+    Prop,
+    /// Sensor volumes. They detect, but never block.
+    Trigger,
+    Npc,
+    /// A prop the player is carrying. Ignores the player and is never stood on.
+    HeldProp,
+    /// Sensor shapes of interactables that have no collider of their own. Only the interaction
+    /// ray sees them.
+    Interaction,
+}
+
+impl GameLayer {
+    pub fn player() -> CollisionLayers {
+        CollisionLayers::new(
+            GameLayer::Player,
+            [
+                GameLayer::Default,
+                GameLayer::World,
+                GameLayer::Prop,
+                GameLayer::Trigger,
+                GameLayer::Npc,
+            ],
+        )
+    }
+
+    pub fn held_prop() -> CollisionLayers {
+        CollisionLayers::new(
+            GameLayer::HeldProp,
+            [
+                GameLayer::Default,
+                GameLayer::World,
+                GameLayer::Prop,
+                GameLayer::Trigger,
+                GameLayer::Npc,
+                GameLayer::HeldProp,
+            ],
+        )
+    }
+
+    pub fn world() -> CollisionLayers {
+        CollisionLayers::new(GameLayer::World, LayerMask::ALL)
+    }
+
+    pub fn prop() -> CollisionLayers {
+        CollisionLayers::new(GameLayer::Prop, LayerMask::ALL)
+    }
+
+    pub fn trigger() -> CollisionLayers {
+        CollisionLayers::new(
+            GameLayer::Trigger,
+            [
+                GameLayer::Player,
+                GameLayer::Prop,
+                GameLayer::Npc,
+                GameLayer::HeldProp,
+            ],
+        )
+    }
+
+    pub fn npc() -> CollisionLayers {
+        CollisionLayers::new(
+            GameLayer::Npc,
+            [
+                GameLayer::Default,
+                GameLayer::World,
+                GameLayer::Player,
+                GameLayer::Prop,
+                GameLayer::Trigger,
+                GameLayer::HeldProp,
+            ],
+        )
+    }
+
+    pub fn interaction_volume() -> CollisionLayers {
+        CollisionLayers::new(GameLayer::Interaction, LayerMask::NONE)
+    }
+
+    /// What the player can stand on.
+    pub fn ground_mask() -> LayerMask {
+        [GameLayer::Default, GameLayer::World, GameLayer::Prop].into()
+    }
+
+    /// What the interaction ray can hit. World geometry is included so it blocks the ray.
+    pub fn interact_mask() -> LayerMask {
+        [
+            GameLayer::Default,
+            GameLayer::World,
+            GameLayer::Prop,
+            GameLayer::Npc,
+            GameLayer::Interaction,
+        ]
+        .into()
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::Deserialize;
+
+use super::{QuestDef, QuestDefs};
+use crate::ron_asset::RonAsset;
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct QuestFile {
+    pub quests: Vec<QuestDef>,
+}
+
+impl RonAsset for QuestFile {
+    const EXTENSION: &'static str = "quest.ron";
+    const FOLDER: &'static str = "quests";
+    type Merged = QuestDefs;
+
+    /// The first file to define a quest wins. Quests with no stages are skipped.
+    fn merge(files: &[(String, &Self)], defs: &mut QuestDefs) {
+        let mut quests = HashMap::new();
+        for (path, file) in files.iter() {
+            for def in file.quests.iter() {
+                if def.stages.is_empty() {
+                    warn!("{}: quest {:?} has no stages", path, def.id);
+                    continue;
+                }
+                if quests.contains_key(&def.id) {
+                    warn!(
+                        "{}: quest {:?} is already defined in another file",
+                        path, def.id
+                    );
+                    continue;
+                }
+                quests.insert(def.id.clone(), def.clone());
+            }
+        }
+        info!("Quest definitions rebuilt: {} quests", quests.len());
+        defs.0 = quests;
+    }
+}
@@ -0,0 +1,472 @@
+use std::collections::BTreeMap;
+
+use bevy::{ecs::system::SystemParam, platform::collections::HashMap, prelude::*};
+use bevy_egui::EguiPrimaryContextPass;
+use serde::{Deserialize, Serialize};
+
+use crate::components::Player;
+use crate::dialog::vars::{DialogEffect, DialogVars};
+use crate::dialog::{DialogActionsExt, DialogStarted, NodeEntered, Npc};
+use crate::door::DoorOpened;
+use crate::inventory::{Inventory, InventoryChanged};
+use crate::ron_asset::{RonAssetAppExt, RonAssetsRebuilt};
+use crate::trigger::{Trigger, TriggerEntered};
+
+pub mod loader;
+pub mod save;
+mod ui;
+
+/// What an objective asks of the player, e.g. `TalkTo("Guard")` or `Collect("coin", 3)`.
+#[derive(Clone, Deserialize, Debug)]
+pub enum Goal {
+    /// Start a conversation with the NPC of this name.
+    TalkTo(String),
+    /// See this dialog node.
+    DialogNode(String),
+    /// Enter the trigger with this id.
+    ReachTrigger(String),
+    /// Carry at least this many of the item.
+    Collect(String, u32),
+    /// The door or gate with this id opens, however it is opened.
+    OpenGate(String),
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Objective {
+    /// Shown in the tracker and journal.
+    pub text: String,
+    pub goal: Goal,
+}
+
+/// One step of a quest. It is done when all its objectives are.
+#[derive(Clone, Deserialize, Debug)]
+pub struct QuestStage {
+    pub text: String,
+    pub objectives: Vec<Objective>,
+    /// Applied to the dialog variables when the stage is done, so dialog can react to it.
+    #[serde(default)]
+    pub effects: Vec<DialogEffect>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct QuestDef {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub stages: Vec<QuestStage>,
+    /// Start the quest as soon as it is loaded, instead of from dialog.
+    #[serde(default)]
+    pub auto_start: bool,
+}
+
+/// Every quest definition, by id.
+#[derive(Resource, Default)]
+pub struct QuestDefs(pub HashMap<String, QuestDef>);
+
+/// How far the player is through one quest.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct QuestProgress {
+    /// Index into the quest's stages.
+    pub stage: usize,
+    /// Which objectives of the current stage are done.
+    pub done: Vec<bool>,
+    pub completed: bool,
+}
+
+/// Every quest the player has started. Saved as part of [`save::SaveGame`].
+#[derive(Resource, Clone, Serialize, Deserialize, Default, Debug)]
+pub struct QuestLog {
+    pub quests: BTreeMap<String, QuestProgress>,
+    /// The quest shown in the tracker.
+    pub tracked: Option<String>,
+}
+
+impl QuestLog {
+    pub fn get(&self, id: &str) -> Option<&QuestProgress> {
+        self.quests.get(id)
+    }
+
+    pub fn is_active(&self, id: &str) -> bool {
+        self.get(id).is_some_and(|p| !p.completed)
+    }
+
+    pub fn is_completed(&self, id: &str) -> bool {
+        self.get(id).is_some_and(|p| p.completed)
+    }
+
+    /// Starts the quest at its first stage. Returns false if it was already started.
+    pub fn start(&mut self, def: &QuestDef) -> bool {
+        if self.quests.contains_key(&def.id) {
+            return false;
+        }
+        self.quests.insert(
+            def.id.clone(),
+            QuestProgress {
+                stage: 0,
+                done: vec![false; def.stages[0].objectives.len()],
+                completed: false,
+            },
+        );
+        if self.tracked.is_none() {
+            self.tracked = Some(def.id.clone());
+        }
+        true
+    }
+
+    /// Tracks the first active quest, if the tracked one is gone or done. Returns true if the
+    /// tracked quest changed.
+    fn retrack(&mut self) -> bool {
+        if self.tracked.as_deref().is_some_and(|id| self.is_active(id)) {
+            return false;
+        }
+        let tracked = self
+            .quests
+            .iter()
+            .find(|(_, p)| !p.completed)
+            .map(|(id, _)| id.clone());
+        if tracked == self.tracked {
+            return false;
+        }
+        self.tracked = tracked;
+        true
+    }
+}
+
+#[derive(Message, Clone, Debug)]
+pub struct QuestStarted {
+    pub quest: String,
+}
+
+#[derive(Message, Clone, Debug)]
+pub struct ObjectiveCompleted {
+    pub quest: String,
+    pub stage: usize,
+    /// Index into the stage's objectives.
+    pub objective: usize,
+}
+
+/// The quest moved on to `stage`.
+#[derive(Message, Clone, Debug)]
+pub struct QuestAdvanced {
+    pub quest: String,
+    pub stage: usize,
+}
+
+#[derive(Message, Clone, Debug)]
+pub struct QuestCompleted {
+    pub quest: String,
+}
+
+#[derive(SystemParam)]
+pub struct QuestMessages<'w> {
+    pub objective: MessageWriter<'w, ObjectiveCompleted>,
+    pub advanced: MessageWriter<'w, QuestAdvanced>,
+    pub completed: MessageWriter<'w, QuestCompleted>,
+}
+
+/// Something that happened in the world this frame that an objective may be waiting for.
+enum Happened {
+    Talked(String),
+    Node(String),
+    Trigger(String),
+    Gate(String),
+}
+
+impl Goal {
+    fn matches(&self, happened: &Happened) -> bool {
+        match (self, happened) {
+            (Goal::TalkTo(a), Happened::Talked(b))
+            | (Goal::DialogNode(a), Happened::Node(b))
+            | (Goal::ReachTrigger(a), Happened::Trigger(b))
+            | (Goal::OpenGate(a), Happened::Gate(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+pub struct QuestPlugin;
+
+impl Plugin for QuestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_ron_asset::<loader::QuestFile>()
+            .init_resource::<QuestDefs>()
+            .init_resource::<QuestLog>()
+            .init_resource::<ui::JournalScreen>()
+            .add_message::<QuestStarted>()
+            .add_message::<ObjectiveCompleted>()
+            .add_message::<QuestAdvanced>()
+            .add_message::<QuestCompleted>()
+            .register_dialog_action("start_quest", start_quest_action)
+            .add_systems(
+                Update,
+                (auto_start_quests_system, quest_progress_system)
+                    .chain()
+                    .after(RonAssetsRebuilt),
+            )
+            .add_systems(Update, (ui::toggle_journal_system, save::save_keys_system))
+            .add_systems(
+                EguiPrimaryContextPass,
+                (ui::quest_tracker_system, ui::journal_egui_system),
+            );
+    }
+}
+
+/// `start_quest <quest id>`
+fn start_quest_action(
+    In(args): In<Vec<String>>,
+    defs: Res<QuestDefs>,
+    mut log: ResMut<QuestLog>,
+    mut started: MessageWriter<QuestStarted>,
+) {
+    let Some(id) = args.first() else {
+        warn!("start_quest expects a quest id");
+        return;
+    };
+    let Some(def) = defs.0.get(id) else {
+        warn!("start_quest: unknown quest {:?}", id);
+        return;
+    };
+    if log.start(def) {
+        info!("Quest started: {}", def.title);
+        started.write(QuestStarted { quest: id.clone() });
+    }
+}
+
+fn auto_start_quests_system(
+    defs: Res<QuestDefs>,
+    mut log: ResMut<QuestLog>,
+    mut started: MessageWriter<QuestStarted>,
+) {
+    if !defs.is_changed() {
+        return;
+    }
+    let mut ids: Vec<&String> = defs.0.keys().collect();
+    ids.sort();
+    for id in ids {
+        let def = &defs.0[id];
+        if def.auto_start && log.start(def) {
+            info!("Quest started: {}", def.title);
+            started.write(QuestStarted { quest: id.clone() });
+        }
+    }
+}
+
+/// Marks objectives done from this frame's dialog, trigger, door and inventory messages, and
+/// moves quests on to their next stage once every objective of the current one is done.
+///
+/// `Collect` objectives are checked against the player's inventory whenever it changes or the
+/// quest log does, so items picked up before a quest starts count towards it.
+#[allow(clippy::too_many_arguments)]
+fn quest_progress_system(
+    defs: Res<QuestDefs>,
+    mut log: ResMut<QuestLog>,
+    mut vars: ResMut<DialogVars>,
+    mut dialog_started: MessageReader<DialogStarted>,
+    mut nodes_entered: MessageReader<NodeEntered>,
+    mut triggers_entered: MessageReader<TriggerEntered>,
+    mut doors_opened: MessageReader<DoorOpened>,
+    mut inventory_changes: MessageReader<InventoryChanged>,
+    npc_q: Query<&Npc>,
+    trigger_q: Query<&Trigger>,
+    player_q: Query<(Entity, &Inventory), With<Player>>,
+    mut messages: QuestMessages,
+) {
+    let player = player_q.single().ok();
+    let player_e = player.map(|(e, _)| e);
+    let inventory = player.map(|(_, inv)| inv);
+
+    let mut happened = Vec::new();
+    for ev in dialog_started.read() {
+        if let Some(npc) = ev.npc.and_then(|e| npc_q.get(e).ok()) {
+            happened.push(Happened::Talked(npc.name.clone()));
+        }
+    }
+    for ev in nodes_entered.read() {
+        happened.push(Happened::Node(ev.node.clone()));
+    }
+    for ev in triggers_entered.read() {
+        if Some(ev.entity) != player_e {
+            continue;
+        }
+        if let Ok(trigger) = trigger_q.get(ev.trigger) {
+            happened.push(Happened::Trigger(trigger.id.clone()));
+        }
+    }
+    for ev in doors_opened.read() {
+        happened.push(Happened::Gate(ev.id.clone()));
+    }
+    let items_changed = inventory_changes
+        .read()
+        .any(|change| Some(change.entity) == player_e);
+    let check_items = items_changed || log.is_changed();
+    if happened.is_empty() && !check_items {
+        return;
+    }
+
+    // Only mark the log changed when progress is made, or `check_items` stays true every frame.
+    let log_mut = log.bypass_change_detection();
+    let mut changed = false;
+    for (id, progress) in log_mut.quests.iter_mut() {
+        if progress.completed {
+            continue;
+        }
+        let Some(def) = defs.0.get(id) else {
+            continue;
+        };
+        changed |= advance_quest(
+            def,
+            progress,
+            &happened,
+            check_items,
+            inventory,
+            &mut vars,
+            &mut messages,
+        );
+    }
+    changed |= log_mut.retrack();
+    if changed {
+        log.set_changed();
+    }
+}
+
+/// Returns true if `progress` changed. `vars` stays a `ResMut` so it is only marked changed when
+/// an effect is applied.
+fn advance_quest(
+    def: &QuestDef,
+    progress: &mut QuestProgress,
+    happened: &[Happened],
+    mut check_items: bool,
+    inventory: Option<&Inventory>,
+    vars: &mut ResMut<DialogVars>,
+    messages: &mut QuestMessages,
+) -> bool {
+    let mut happened = happened;
+    let mut changed = false;
+    while let Some(stage) = def.stages.get(progress.stage) {
+        // The definition may have changed since the progress was saved.
+        if progress.done.len() != stage.objectives.len() {
+            progress.done.resize(stage.objectives.len(), false);
+            changed = true;
+        }
+        for (i, objective) in stage.objectives.iter().enumerate() {
+            if progress.done[i] {
+                continue;
+            }
+            let done = match &objective.goal {
+                Goal::Collect(item, count) => {
+                    check_items && inventory.is_some_and(|inv| inv.count(item) >= *count)
+                }
+                goal => happened.iter().any(|h| goal.matches(h)),
+            };
+            if done {
+                progress.done[i] = true;
+                changed = true;
+                messages.objective.write(ObjectiveCompleted {
+                    quest: def.id.clone(),
+                    stage: progress.stage,
+                    objective: i,
+                });
+            }
+        }
+        if !progress.done.iter().all(|d| *d) {
+            return changed;
+        }
+
+        for effect in stage.effects.iter() {
+            effect.apply(vars);
+        }
+        progress.stage += 1;
+        let Some(next) = def.stages.get(progress.stage) else {
+            break;
+        };
+        info!("Quest {}: {}", def.title, next.text);
+        progress.done = vec![false; next.objectives.len()];
+        messages.advanced.write(QuestAdvanced {
+            quest: def.id.clone(),
+            stage: progress.stage,
+        });
+        // What happened this frame was for the previous stage.
+        happened = &[];
+        check_items = true;
+    }
+    info!("Quest completed: {}", def.title);
+    progress.completed = true;
+    messages.completed.write(QuestCompleted {
+        quest: def.id.clone(),
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// How many frames `QuestLog` was seen as changed.
+    #[derive(Resource, Default)]
+    struct LogChanges(u32);
+
+    fn count_log_changes(log: Res<QuestLog>, mut changes: ResMut<LogChanges>) {
+        if log.is_changed() {
+            changes.0 += 1;
+        }
+    }
+
+    fn app_with_quest(coins: u32) -> App {
+        let mut app = App::new();
+        app.add_message::<DialogStarted>()
+            .add_message::<NodeEntered>()
+            .add_message::<TriggerEntered>()
+            .add_message::<DoorOpened>()
+            .add_message::<InventoryChanged>()
+            .add_message::<ObjectiveCompleted>()
+            .add_message::<QuestAdvanced>()
+            .add_message::<QuestCompleted>()
+            .init_resource::<QuestDefs>()
+            .init_resource::<QuestLog>()
+            .init_resource::<DialogVars>()
+            .init_resource::<LogChanges>()
+            .add_systems(Update, (quest_progress_system, count_log_changes).chain());
+        let def = QuestDef {
+            id: "coins".to_string(),
+            title: "Coins".to_string(),
+            description: String::new(),
+            stages: vec![QuestStage {
+                text: "Find some coins".to_string(),
+                objectives: vec![Objective {
+                    text: "Find coins".to_string(),
+                    goal: Goal::Collect("coin".to_string(), 2),
+                }],
+                effects: Vec::new(),
+            }],
+            auto_start: false,
+        };
+        app.world_mut().resource_mut::<QuestLog>().start(&def);
+        app.world_mut()
+            .resource_mut::<QuestDefs>()
+            .0
+            .insert(def.id.clone(), def);
+        let mut inventory = Inventory::default();
+        inventory.add("coin", coins);
+        app.world_mut().spawn((Player, inventory));
+        app
+    }
+
+    #[test]
+    fn items_carried_before_the_quest_count() {
+        let mut app = app_with_quest(2);
+        app.update();
+        assert!(app.world().resource::<QuestLog>().is_completed("coins"));
+    }
+
+    #[test]
+    fn log_is_not_changed_without_progress() {
+        let mut app = app_with_quest(1);
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!(app.world().resource::<QuestLog>().is_active("coins"));
+        // Only the first frame, which sees the quest being started.
+        assert_eq!(app.world().resource::<LogChanges>().0, 1);
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::Path,
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::QuestLog;
+use crate::components::Player;
+use crate::dialog::vars::{DialogValue, DialogVars};
+use crate::door::{Door, DoorState};
+use crate::inventory::pickup::CollectedPickups;
+use crate::inventory::Inventory;
+
+/// Where F5 saves the game and F9 loads it from, relative to the working directory.
+pub const SAVE_PATH: &str = "saves/game.ron";
+
+const SAVE_KEY: KeyCode = KeyCode::F5;
+const LOAD_KEY: KeyCode = KeyCode::F9;
+
+/// Everything that is saved: quest progress, dialog variables, collected pickups, the player's
+/// inventory and which doors are open or locked.
+///
+/// Sorted collections keep the file stable between saves.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct SaveGame {
+    pub quests: QuestLog,
+    #[serde(default)]
+    pub vars: BTreeMap<String, DialogValue>,
+    /// Dialog nodes the player has seen.
+    #[serde(default)]
+    pub visited: BTreeSet<String>,
+    /// Names of the collected pickups.
+    #[serde(default)]
+    pub pickups: BTreeSet<String>,
+    /// Item counts in the player's inventory.
+    #[serde(default)]
+    pub inventory: BTreeMap<String, u32>,
+    /// By [`Door::id`].
+    #[serde(default)]
+    pub doors: BTreeMap<String, SavedDoor>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SavedDoor {
+    pub open: bool,
+    pub locked: bool,
+}
+
+/// The world state that goes into a [`SaveGame`].
+#[derive(SystemParam)]
+pub struct Saved<'w, 's> {
+    log: ResMut<'w, QuestLog>,
+    vars: ResMut<'w, DialogVars>,
+    pickups: ResMut<'w, CollectedPickups>,
+    player_q: Query<'w, 's, &'static mut Inventory, With<Player>>,
+    door_q: Query<'w, 's, (&'static mut Door, &'static mut DoorState)>,
+}
+
+impl Saved<'_, '_> {
+    pub fn capture(&self) -> SaveGame {
+        SaveGame {
+            quests: self.log.clone(),
+            vars: self
+                .vars
+                .values
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            visited: self.vars.visited.iter().cloned().collect(),
+            pickups: self.pickups.0.iter().cloned().collect(),
+            inventory: self
+                .player_q
+                .single()
+                .map(|inv| {
+                    inv.iter()
+                        .map(|(item, count)| (item.to_string(), count))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            doors: self
+                .door_q
+                .iter()
+                .filter(|(door, _)| !door.id.is_empty())
+                .map(|(door, state)| {
+                    let saved = SavedDoor {
+                        open: state.is_open(),
+                        locked: door.locked,
+                    };
+                    (door.id.clone(), saved)
+                })
+                .collect(),
+        }
+    }
+
+    /// Replaces the current state with the saved one. Pickups already in the level are only
+    /// removed or brought back the next time it is loaded.
+    pub fn restore(&mut self, save: SaveGame) {
+        *self.log = save.quests;
+        self.vars.values = save.vars.into_iter().collect();
+        self.vars.visited = save.visited.into_iter().collect();
+        self.pickups.0 = save.pickups.into_iter().collect();
+        if let Ok(mut inventory) = self.player_q.single_mut() {
+            *inventory = Inventory::default();
+            for (item, count) in save.inventory.iter() {
+                inventory.add(item, *count);
+            }
+        }
+        for (mut door, mut state) in self.door_q.iter_mut() {
+            if let Some(saved) = save.doors.get(&door.id) {
+                door.locked = saved.locked;
+                state.set_open(saved.open);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "could not access save: {}", err),
+            SaveError::Serialize(err) => write!(f, "could not write save: {}", err),
+            SaveError::Parse(err) => write!(f, "could not read save: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+pub fn save_game(save: &SaveGame, path: &Path) -> Result<(), SaveError> {
+    let text = ron::ser::to_string_pretty(save, ron::ser::PrettyConfig::default())
+        .map_err(SaveError::Serialize)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text)?;
+    Ok(())
+}
+
+pub fn load_game(path: &Path) -> Result<SaveGame, SaveError> {
+    let text = fs::read_to_string(path)?;
+    ron::from_str(&text).map_err(SaveError::Parse)
+}
+
+/// F5 saves the game, F9 loads it back.
+pub fn save_keys_system(keys: Res<ButtonInput<KeyCode>>, mut saved: Saved) {
+    let path = Path::new(SAVE_PATH);
+    if keys.just_pressed(SAVE_KEY) {
+        match save_game(&saved.capture(), path) {
+            Ok(()) => info!("Game saved to {}", path.display()),
+            Err(err) => error!("{}", err),
+        }
+    }
+    if keys.just_pressed(LOAD_KEY) {
+        match load_game(path) {
+            Ok(save) => {
+                saved.restore(save);
+                info!("Game loaded from {}", path.display());
+            }
+            Err(err) => error!("{}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn save_round_trips_the_world() {
+        let mut app = App::new();
+        app.init_resource::<QuestLog>()
+            .init_resource::<DialogVars>()
+            .init_resource::<CollectedPickups>();
+        let mut inventory = Inventory::default();
+        inventory.add("coin", 3);
+        let player = app.world_mut().spawn((Player, inventory)).id();
+        let mut state = DoorState::default();
+        state.set_open(true);
+        let door = Door {
+            id: "east_gate".to_string(),
+            ..default()
+        };
+        let door_e = app.world_mut().spawn((door, state)).id();
+        {
+            let world = app.world_mut();
+            let mut vars = world.resource_mut::<DialogVars>();
+            vars.set("gate_opened", DialogValue::Bool(true));
+            vars.set("coins_paid", DialogValue::Int(3));
+            vars.visited.insert("root".to_string());
+            world
+                .resource_mut::<CollectedPickups>()
+                .0
+                .insert("Coin.001".to_string());
+        }
+
+        let save = app
+            .world_mut()
+            .run_system_once(|saved: Saved| saved.capture())
+            .unwrap();
+        let text = ron::to_string(&save).unwrap();
+        let loaded: SaveGame = ron::from_str(&text).unwrap();
+
+        // Lose everything, then load.
+        let world = app.world_mut();
+        *world.resource_mut::<DialogVars>() = DialogVars::default();
+        *world.resource_mut::<CollectedPickups>() = CollectedPickups::default();
+        *world.get_mut::<Inventory>(player).unwrap() = Inventory::default();
+        world.get_mut::<Door>(door_e).unwrap().locked = true;
+        world.get_mut::<DoorState>(door_e).unwrap().set_open(false);
+        world
+            .run_system_once(move |mut saved: Saved| saved.restore(loaded.clone()))
+            .unwrap();
+
+        let world = app.world();
+        let vars = world.resource::<DialogVars>();
+        assert!(vars.flag("gate_opened"));
+        assert_eq!(vars.int("coins_paid"), 3);
+        assert!(vars.visited.contains("root"));
+        assert!(world.resource::<CollectedPickups>().0.contains("Coin.001"));
+        assert_eq!(world.get::<Inventory>(player).unwrap().count("coin"), 3);
+        assert!(!world.get::<Door>(door_e).unwrap().locked);
+        assert!(world.get::<DoorState>(door_e).unwrap().is_open());
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use super::{Goal, Objective, QuestDefs, QuestLog};
+use crate::components::Player;
+use crate::dialog::DialogState;
+use crate::inventory::Inventory;
+
+const TOGGLE_KEY: KeyCode = KeyCode::KeyJ;
+
+#[derive(Resource, Default)]
+pub struct JournalScreen {
+    pub open: bool,
+    /// The quest whose details are shown.
+    selected: Option<String>,
+}
+
+pub fn toggle_journal_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    dialog_state: Res<DialogState>,
+    mut journal: ResMut<JournalScreen>,
+) {
+    if keys.just_pressed(TOGGLE_KEY) && !dialog_state.open {
+        journal.open = !journal.open;
+    }
+}
+
+/// The objective's text, with the player's progress for `Collect` objectives, e.g. "Find coins (2/3)".
+fn objective_text(objective: &Objective, inventory: Option<&Inventory>) -> String {
+    match &objective.goal {
+        Goal::Collect(item, count) => {
+            let have = inventory.map_or(0, |inv| inv.count(item)).min(*count);
+            format!("{} ({}/{})", objective.text, have, count)
+        }
+        _ => objective.text.clone(),
+    }
+}
+
+fn objective_label(
+    ui: &mut egui::Ui,
+    objective: &Objective,
+    done: bool,
+    inventory: Option<&Inventory>,
+) {
+    let text = objective_text(objective, inventory);
+    if done {
+        ui.label(
+            egui::RichText::new(format!("[x] {}", text))
+                .strikethrough()
+                .color(egui::Color32::GRAY),
+        );
+    } else {
+        ui.label(format!("[ ] {}", text));
+    }
+}
+
+/// Shows the tracked quest's current stage and objectives in the top right corner.
+pub fn quest_tracker_system(
+    mut contexts: EguiContexts,
+    defs: Res<QuestDefs>,
+    log: Res<QuestLog>,
+    dialog_state: Res<DialogState>,
+    player_q: Query<&Inventory, With<Player>>,
+) {
+    if dialog_state.open {
+        return;
+    }
+    let Some(id) = log.tracked.as_deref() else {
+        return;
+    };
+    let (Some(def), Some(progress)) = (defs.0.get(id), log.get(id)) else {
+        return;
+    };
+    let Some(stage) = def.stages.get(progress.stage) else {
+        return;
+    };
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+    let inventory = player_q.single().ok();
+    egui::Area::new(egui::Id::new("quest_tracker"))
+        .anchor(egui::Align2::RIGHT_TOP, [-16.0, 16.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_max_width(280.0);
+                ui.label(
+                    egui::RichText::new(def.title.as_str())
+                        .strong()
+                        .color(egui::Color32::YELLOW),
+                );
+                ui.label(stage.text.as_str());
+                for (i, objective) in stage.objectives.iter().enumerate() {
+                    let done = progress.done.get(i).copied().unwrap_or(false);
+                    objective_label(ui, objective, done, inventory);
+                }
+            });
+        });
+}
+
+/// Lists active and completed quests. Selecting one shows its stages so far, and tracks it if it
+/// is active.
+pub fn journal_egui_system(
+    mut contexts: EguiContexts,
+    mut journal: ResMut<JournalScreen>,
+    defs: Res<QuestDefs>,
+    mut log: ResMut<QuestLog>,
+    player_q: Query<&Inventory, With<Player>>,
+) {
+    if !journal.open {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+    let inventory = player_q.single().ok();
+    let mut open = journal.open;
+    let mut track = None;
+    egui::Window::new("Journal")
+        .open(&mut open)
+        .default_size([560.0, 360.0])
+        .show(ctx, |ui| {
+            if log.quests.is_empty() {
+                ui.label("No quests yet.");
+                return;
+            }
+            ui.horizontal_top(|ui| {
+                ui.vertical(|ui| {
+                    ui.set_width(180.0);
+                    for (heading, completed) in [("Active", false), ("Completed", true)] {
+                        ui.label(egui::RichText::new(heading).strong());
+                        for (id, progress) in log.quests.iter() {
+                            if progress.completed != completed {
+                                continue;
+                            }
+                            let title = defs.0.get(id).map_or(id.as_str(), |d| d.title.as_str());
+                            let selected = journal.selected.as_deref() == Some(id.as_str());
+                            if ui.selectable_label(selected, title).clicked() {
+                                journal.selected = Some(id.clone());
+                                if !completed {
+                                    track = Some(id.clone());
+                                }
+                            }
+                        }
+                        ui.add_space(8.0);
+                    }
+                });
+                ui.separator();
+                ui.vertical(|ui| {
+                    let Some(id) = journal.selected.as_deref() else {
+                        ui.label("Select a quest.");
+                        return;
+                    };
+                    let (Some(def), Some(progress)) = (defs.0.get(id), log.get(id)) else {
+                        return;
+                    };
+                    ui.heading(def.title.as_str());
+                    if log.tracked.as_deref() == Some(id) {
+                        ui.label(
+                            egui::RichText::new("Tracked")
+                                .small()
+                                .color(egui::Color32::YELLOW),
+                        );
+                    }
+                    if !def.description.is_empty() {
+                        ui.label(def.description.as_str());
+                    }
+                    ui.add_space(8.0);
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (i, stage) in def.stages.iter().enumerate() {
+                            if i < progress.stage || progress.completed {
+                                ui.label(
+                                    egui::RichText::new(stage.text.as_str())
+                                        .color(egui::Color32::GRAY),
+                                );
+                            } else if i == progress.stage {
+                                ui.label(stage.text.as_str());
+                                for (j, objective) in stage.objectives.iter().enumerate() {
+                                    let done = progress.done.get(j).copied().unwrap_or(false);
+                                    objective_label(ui, objective, done, inventory);
+                                }
+                            }
+                        }
+                        if progress.completed {
+                            ui.label(egui::RichText::new("Completed").strong());
+                        }
+                    });
+                });
+            });
+        });
+    journal.open = open;
+    if track.is_some() {
+        log.tracked = track;
+    }
+}
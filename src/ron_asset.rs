@@ -0,0 +1,158 @@
+use std::{fmt, marker::PhantomData};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext, LoadedFolder},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
+
+/// An asset read from a RON file, such as a dialog or item file. Every file of the type is
+/// loaded from one folder and merged into one resource.
+pub trait RonAsset: Asset + DeserializeOwned {
+    /// File extension, without the leading dot, e.g. `"dialog.ron"`.
+    const EXTENSION: &'static str;
+    /// Folder under `assets/` the files are loaded from.
+    const FOLDER: &'static str;
+    /// What the files are merged into.
+    type Merged: Resource;
+
+    /// Rebuilds `merged` from every loaded file. `files` are `(asset path, file)` pairs sorted by
+    /// path, so the first file to define an id is the same whatever order they loaded in.
+    fn merge(files: &[(String, &Self)], merged: &mut Self::Merged);
+}
+
+#[derive(Debug)]
+pub enum RonLoadError {
+    Io(std::io::Error),
+    Parse {
+        path: String,
+        line: usize,
+        col: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for RonLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RonLoadError::Io(err) => write!(f, "could not read file: {}", err),
+            RonLoadError::Parse {
+                path,
+                line,
+                col,
+                message,
+            } => write!(f, "{}:{}:{}: {}", path, line, col, message),
+        }
+    }
+}
+
+impl std::error::Error for RonLoadError {}
+
+impl From<std::io::Error> for RonLoadError {
+    fn from(err: std::io::Error) -> Self {
+        RonLoadError::Io(err)
+    }
+}
+
+/// Parses the contents of a RON file. `path` is only used for error messages.
+///
+/// `Option` fields may be written without `Some(..)`.
+pub fn parse_ron<T: DeserializeOwned>(bytes: &[u8], path: &str) -> Result<T, RonLoadError> {
+    ron::Options::default()
+        .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+        .from_bytes(bytes)
+        .map_err(|err| RonLoadError::Parse {
+            path: path.to_string(),
+            line: err.position.line,
+            col: err.position.col,
+            message: err.code.to_string(),
+        })
+}
+
+/// Loads `A` from files ending in [`RonAsset::EXTENSION`].
+pub struct RonAssetLoader<A>(PhantomData<fn() -> A>);
+
+impl<A> Default for RonAssetLoader<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A: RonAsset> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = RonLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        parse_ron(&bytes, &load_context.path().display().to_string())
+    }
+
+    fn extensions(&self) -> &[&str] {
+        std::slice::from_ref(&A::EXTENSION)
+    }
+}
+
+/// Keeps `A`'s folder loaded (and watched for changes).
+#[derive(Resource)]
+pub struct RonFolder<A: RonAsset> {
+    pub handle: Handle<LoadedFolder>,
+    marker: PhantomData<fn() -> A>,
+}
+
+fn load_ron_folder<A: RonAsset>(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(RonFolder::<A> {
+        handle: asset_server.load_folder(A::FOLDER),
+        marker: PhantomData,
+    });
+}
+
+/// Runs [`RonAsset::merge`] whenever a file is added, changed or removed.
+fn rebuild_ron_system<A: RonAsset>(
+    mut events: MessageReader<AssetEvent<A>>,
+    files: Res<Assets<A>>,
+    asset_server: Res<AssetServer>,
+    mut merged: ResMut<A::Merged>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+    let mut sorted: Vec<(String, &A)> = files
+        .iter()
+        .map(|(id, file)| {
+            let path = asset_server
+                .get_path(id)
+                .map(|p| p.to_string())
+                .unwrap_or_default();
+            (path, file)
+        })
+        .collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    A::merge(&sorted, &mut merged);
+}
+
+/// Systems that rebuild the resources RON assets are merged into. Systems that read those
+/// resources should run after it to see changes the same frame.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RonAssetsRebuilt;
+
+pub trait RonAssetAppExt {
+    /// Registers `A` as an asset with its [`RonAssetLoader`], loads its folder on startup and
+    /// merges its files into [`RonAsset::Merged`], which has to be initialized separately.
+    fn init_ron_asset<A: RonAsset>(&mut self) -> &mut Self;
+}
+
+impl RonAssetAppExt for App {
+    fn init_ron_asset<A: RonAsset>(&mut self) -> &mut Self {
+        self.init_asset::<A>()
+            .init_asset_loader::<RonAssetLoader<A>>()
+            .add_systems(Startup, load_ron_folder::<A>)
+            .add_systems(Update, rebuild_ron_system::<A>.in_set(RonAssetsRebuilt))
+    }
+}
@@ -5,6 +5,8 @@ pub mod prologue;
 
 use bevy::prelude::*;
 
+use crate::dialog::DialogActionsExt;
+
 #[derive(States, Clone, Eq, PartialEq, Debug, Hash, Default)]
 pub enum GameScene {
     #[default]
@@ -22,6 +24,21 @@ impl Plugin for ScenePlugin {
             .add_plugins(splash::SplashPlugin)
             .add_plugins(main_menu::MainMenuPlugin)
             .add_plugins(options::OptionsPlugin)
-            .add_plugins(prologue::ProloguePlugin);
+            .add_plugins(prologue::ProloguePlugin)
+            .register_dialog_action("change_scene", change_scene_action);
     }
 }
+
+/// `change_scene <main_menu|options|prologue>`
+fn change_scene_action(In(args): In<Vec<String>>, mut next_state: ResMut<NextState<GameScene>>) {
+    let scene = match args.first().map(String::as_str) {
+        Some("main_menu") => GameScene::MainMenu,
+        Some("options") => GameScene::Options,
+        Some("prologue") => GameScene::Prologue,
+        other => {
+            warn!("change_scene: unknown scene {:?}", other);
+            return;
+        }
+    };
+    next_state.set(scene);
+}
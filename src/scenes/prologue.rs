@@ -1,6 +1,9 @@
 use crate::{controller_avian::RenderPlayer, scenes::GameScene};
+use crate::colliders::{ColliderReadiness, PendingColliders};
 use crate::components;
+use avian3d::prelude::*;
 use bevy::prelude::*;
+use bevy::scene::SceneInstanceReady;
 
 pub struct ProloguePlugin;
 
@@ -11,24 +14,66 @@ impl Plugin for ProloguePlugin {
                 Update,
                 prologue_update.run_if(in_state(GameScene::Prologue)),
             )
+            .add_systems(
+                Update,
+                prologue_loading
+                    .after(ColliderReadiness)
+                    .run_if(in_state(GameScene::Prologue)),
+            )
             .add_systems(
                 Update,
                 attach_camera_to_player.run_if(in_state(GameScene::Prologue)),
             );
     }
 }
+
+/// The prologue counts as loaded once its glTF scene has spawned and every collider in it
+/// has been built. Until then the player's body is disabled so it cannot fall through the level.
+#[derive(Resource, Default)]
+pub struct PrologueLoaded {
+    pub scene_spawned: bool,
+    pub loaded: bool,
+}
+
 fn prologue_setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>
 ) {
-    commands.spawn(SceneRoot(
-        asset_server.load(GltfAssetLabel::Scene(0).from_asset("levels/World.gltf")),
-    ));
+    commands.insert_resource(PrologueLoaded::default());
+    commands
+        .spawn(SceneRoot(
+            asset_server.load(GltfAssetLabel::Scene(0).from_asset("levels/World.gltf")),
+        ))
+        .observe(|_: On<SceneInstanceReady>, mut state: ResMut<PrologueLoaded>| {
+            state.scene_spawned = true;
+        });
 }
 
 fn prologue_update() {
 }
 
+fn prologue_loading(
+    mut commands: Commands,
+    mut state: ResMut<PrologueLoaded>,
+    pending: Res<PendingColliders>,
+    waiting_q: Query<Entity, (With<components::Player>, Without<RigidBodyDisabled>)>,
+    held_q: Query<Entity, (With<components::Player>, With<RigidBodyDisabled>)>,
+) {
+    if !state.loaded && state.scene_spawned && pending.0 == 0 {
+        state.loaded = true;
+        info!("Prologue loaded");
+    }
+    if state.loaded {
+        for player in held_q.iter() {
+            commands.entity(player).remove::<RigidBodyDisabled>();
+        }
+    } else {
+        for player in waiting_q.iter() {
+            commands.entity(player).insert(RigidBodyDisabled);
+        }
+    }
+}
+
 fn attach_camera_to_player(
     mut commands: Commands,
     player_q: Query<Entity, With<components::Player>>,
@@ -41,3 +86,32 @@ fn attach_camera_to_player(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colliders::{CollidersPlugin, Ground};
+
+    #[test]
+    fn not_loaded_while_a_ground_mesh_is_loading() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), CollidersPlugin))
+            .init_asset::<Mesh>()
+            .insert_resource(PrologueLoaded {
+                scene_spawned: true,
+                loaded: false,
+            })
+            .add_systems(Update, prologue_loading.after(ColliderReadiness));
+        // A handle to a mesh that is never loaded.
+        app.world_mut().spawn((
+            Ground::default(),
+            Transform::default(),
+            Mesh3d(Handle::default()),
+        ));
+        for _ in 0..3 {
+            app.update();
+            assert!(!app.world().resource::<PrologueLoaded>().loaded);
+        }
+        assert_eq!(app.world().resource::<PendingColliders>().0, 1);
+    }
+}
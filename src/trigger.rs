@@ -0,0 +1,151 @@
+use avian3d::prelude::*;
+use bevy::{ecs::entity::EntityHashSet, prelude::*};
+
+use crate::colliders::PhysicsObject;
+use crate::components::Player;
+use crate::dialog::Npc;
+
+/// Turns a glTF node into a sensor volume. The node's meshes define its shape and are hidden.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct Trigger {
+    /// Name other systems (quests, cutscenes, level scripts) use to refer to this trigger.
+    pub id: String,
+    pub player: bool,
+    pub physics_objects: bool,
+    pub npcs: bool,
+    /// Stop firing enter events after the first one. Stay and exit events keep coming for
+    /// whatever entered.
+    pub once: bool,
+    /// Minimum seconds between two enter events.
+    pub cooldown: f32,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            player: true,
+            physics_objects: false,
+            npcs: false,
+            once: false,
+            cooldown: 0.0,
+        }
+    }
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct TriggerEntered {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+/// Sent every frame for each entity that is inside a trigger.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct TriggerStayed {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct TriggerExited {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+#[derive(Component, Default)]
+struct TriggerState {
+    inside: EntityHashSet,
+    spent: bool,
+    cooldown_left: f32,
+}
+
+pub struct TriggerPlugin;
+
+impl Plugin for TriggerPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Trigger>()
+            .add_message::<TriggerEntered>()
+            .add_message::<TriggerStayed>()
+            .add_message::<TriggerExited>()
+            .add_observer(|add: On<Add, Trigger>, mut commands: Commands| {
+                commands.entity(add.entity).insert((
+                    TriggerState::default(),
+                    CollidingEntities::default(),
+                    Visibility::Hidden,
+                ));
+            })
+            .add_systems(Update, trigger_events_system);
+    }
+}
+
+fn trigger_events_system(
+    time: Res<Time>,
+    mut trigger_q: Query<(Entity, &Trigger, &mut TriggerState, &CollidingEntities)>,
+    collider_of_q: Query<&ColliderOf>,
+    kind_q: Query<(Has<Player>, Has<PhysicsObject>, Has<Npc>)>,
+    mut entered: MessageWriter<TriggerEntered>,
+    mut stayed: MessageWriter<TriggerStayed>,
+    mut exited: MessageWriter<TriggerExited>,
+) {
+    for (trigger_e, trigger, mut state, colliding) in trigger_q.iter_mut() {
+        state.cooldown_left = (state.cooldown_left - time.delta_secs()).max(0.0);
+
+        // Colliders may be children of the body we care about.
+        let current: EntityHashSet = colliding
+            .iter()
+            .map(|&c| collider_of_q.get(c).map(|of| of.body).unwrap_or(c))
+            .filter(|&e| {
+                let Ok((is_player, is_prop, is_npc)) = kind_q.get(e) else {
+                    return false;
+                };
+                (trigger.player && is_player)
+                    || (trigger.physics_objects && is_prop)
+                    || (trigger.npcs && is_npc)
+            })
+            .collect();
+
+        let left: Vec<Entity> = state
+            .inside
+            .iter()
+            .copied()
+            .filter(|e| !current.contains(e))
+            .collect();
+        for entity in left {
+            state.inside.remove(&entity);
+            exited.write(TriggerExited {
+                trigger: trigger_e,
+                entity,
+            });
+        }
+
+        let mut any_entered = false;
+        for &entity in current.iter() {
+            if state.inside.contains(&entity) {
+                stayed.write(TriggerStayed {
+                    trigger: trigger_e,
+                    entity,
+                });
+                continue;
+            }
+            if state.cooldown_left > 0.0 || state.spent {
+                continue;
+            }
+            state.inside.insert(entity);
+            state.cooldown_left = trigger.cooldown;
+            any_entered = true;
+            info!(
+                "Trigger {:?} ({}) entered by {:?}",
+                trigger_e, trigger.id, entity
+            );
+            entered.write(TriggerEntered {
+                trigger: trigger_e,
+                entity,
+            });
+        }
+        // Set after the loop so everything entering on the same frame as the first gets in.
+        if any_entered && trigger.once {
+            state.spent = true;
+        }
+    }
+}